@@ -1,32 +1,166 @@
+#[cfg(test)]
+mod naive;
+
 use anyhow::Result;
-use itertools::Itertools;
+use harness::{AnswerCheck, Args, Timings};
+use std::collections::HashSet;
 const TOTAL: i32 = 2020;
 
 fn main() -> Result<()> {
+    // Usage: day1 [--time | --bench] [--record]
+    let args = Args::parse(std::env::args().skip(1));
+    let mut timings = Timings::new(args.mode);
     let s = include_str!("../input_data.txt");
-    let lines = s.split('\n');
-    let numbers: Vec<i32> = lines.map(str::parse::<i32>).map(Result::unwrap).collect();
-    let (a, b) = get_pairs_from_numbers(numbers.iter()).unwrap();
+    let mut answers = AnswerCheck::new(
+        env!("CARGO_MANIFEST_DIR"),
+        env!("CARGO_PKG_NAME"),
+        s,
+        args.record,
+    );
+    let numbers = timings.time("parse", || parsing::numbers::<i32>(s))?;
+    let (a, b) = timings
+        .time("part a", || get_pairs_from_numbers(&numbers))
+        .unwrap();
     println!(
         "part a: Our numbers are {:?} which multiply to {}",
         (a, b),
         a * b
     );
-    let (a, b, c) = get_triples_from_numbers(numbers.iter()).unwrap();
+    answers.check("a", a * b);
+    let (a, b, c) = timings
+        .time("part b", || get_triples_from_numbers(&numbers))
+        .unwrap();
     println!(
         "part b: Our numbers are {:?} which multiply to {}",
         (a, b, c),
         a * b * c
     );
+    answers.check("b", a * b * c);
+    timings.finish(env!("CARGO_MANIFEST_DIR"));
+    answers.finish();
     Ok(())
 }
 
-fn get_pairs_from_numbers(numbers: std::slice::Iter<i32>) -> Option<(&i32, &i32)> {
-    numbers.tuple_combinations().find(|(a, b)| *a + *b == TOTAL)
+/// The first pair adding up to `TOTAL`, found by looking for each number's
+/// complement among the numbers before it.
+fn get_pairs_from_numbers(numbers: &[i32]) -> Option<(i32, i32)> {
+    let mut seen = HashSet::<i32>::new();
+    for b in numbers.iter() {
+        if seen.contains(&(TOTAL - b)) {
+            return Some((TOTAL - b, *b));
+        }
+        seen.insert(*b);
+    }
+    None
+}
+
+/// A triple adding up to `TOTAL`. For each pair we look for the third number
+/// among the ones before the pair, so no entry is used twice.
+fn get_triples_from_numbers(numbers: &[i32]) -> Option<(i32, i32, i32)> {
+    let mut seen = HashSet::<i32>::new();
+    for (i, b) in numbers.iter().enumerate() {
+        for c in numbers[i + 1..].iter() {
+            if seen.contains(&(TOTAL - b - c)) {
+                return Some((TOTAL - b - c, *b, *c));
+            }
+        }
+        seen.insert(*b);
+    }
+    None
 }
 
-fn get_triples_from_numbers(numbers: std::slice::Iter<i32>) -> Option<(&i32, &i32, &i32)> {
-    numbers
-        .tuple_combinations()
-        .find(|(a, b, c)| *a + *b + *c == TOTAL)
+#[cfg(test)]
+mod test {
+    use crate::naive;
+    use crate::{get_pairs_from_numbers, get_triples_from_numbers, TOTAL};
+    use proptest::prelude::*;
+
+    // Whether `found` are entries of `numbers`, each used no more often than it appears
+    fn from_numbers(numbers: &[i32], found: &[i32]) -> bool {
+        found.iter().all(|n| {
+            found.iter().filter(|m| *m == n).count() <= numbers.iter().filter(|m| *m == n).count()
+        })
+    }
+
+    #[test]
+    fn test_example() {
+        let numbers = [1721, 979, 366, 299, 675, 1456];
+        assert_eq!(get_pairs_from_numbers(&numbers), Some((1721, 299)));
+        assert_eq!(get_triples_from_numbers(&numbers), Some((979, 366, 675)));
+        // One 1010 doesn't make a pair on its own
+        assert_eq!(get_pairs_from_numbers(&[1010, 5]), None);
+        assert_eq!(get_pairs_from_numbers(&[1010, 5, 1010]), Some((1010, 1010)));
+    }
+
+    #[test]
+    fn test_generated() {
+        for seed in 0..10 {
+            let generated = generators::day1::generate(seed, 200);
+            let numbers = parsing::numbers::<i32>(&generated.input).unwrap();
+            let (a, b) = get_pairs_from_numbers(&numbers).unwrap();
+            assert_eq!(a * b, generated.part_a);
+            let (a, b, c) = get_triples_from_numbers(&numbers).unwrap();
+            assert_eq!(a * b * c, generated.part_b);
+        }
+    }
+
+    // Random numbers hardly ever have three adding up to `TOTAL`, so plant
+    // one somewhere in the list
+    fn planted_triple() -> impl Strategy<Value = Vec<i32>> {
+        (prop::collection::vec(0..2021, 0..40), 0..=TOTAL)
+            .prop_flat_map(|(numbers, a)| (Just(numbers), Just(a), 0..=TOTAL - a))
+            .prop_map(|(mut numbers, a, b)| {
+                numbers.extend([a, b, TOTAL - a - b]);
+                numbers
+            })
+            .prop_shuffle()
+    }
+
+    // Small numbers so that plenty of lists have more than one answer, in
+    // which case the two versions can find different ones
+    proptest! {
+        #[test]
+        fn test_pairs_match_naive(numbers in prop::collection::vec(0..2021, 0..40)) {
+            let found = get_pairs_from_numbers(&numbers);
+            prop_assert_eq!(found.is_some(), naive::get_pairs_from_numbers(&numbers).is_some());
+            if let Some((a, b)) = found {
+                prop_assert_eq!(a + b, TOTAL);
+                prop_assert!(from_numbers(&numbers, &[a, b]));
+            }
+        }
+
+        #[test]
+        fn test_triples_match_naive(numbers in prop_oneof![
+            prop::collection::vec(0..2021, 0..40),
+            planted_triple()
+        ]) {
+            let found = get_triples_from_numbers(&numbers);
+            prop_assert_eq!(found.is_some(), naive::get_triples_from_numbers(&numbers).is_some());
+            if let Some((a, b, c)) = found {
+                prop_assert_eq!(a + b + c, TOTAL);
+                prop_assert!(from_numbers(&numbers, &[a, b, c]));
+            }
+        }
+
+        #[test]
+        fn test_planted_triple_found(numbers in planted_triple()) {
+            let (a, b, c) = get_triples_from_numbers(&numbers).unwrap();
+            prop_assert_eq!(a + b + c, TOTAL);
+            prop_assert!(from_numbers(&numbers, &[a, b, c]));
+            prop_assert!(naive::get_triples_from_numbers(&numbers).is_some());
+        }
+
+        // The generated reports only have one answer, so both versions must agree exactly
+        #[test]
+        fn test_generated_match_naive(seed in any::<u64>(), size in 5..100usize) {
+            let generated = generators::day1::generate(seed, size);
+            let numbers = parsing::numbers::<i32>(&generated.input).unwrap();
+            let (a, b) = get_pairs_from_numbers(&numbers).unwrap();
+            let (x, y) = naive::get_pairs_from_numbers(&numbers).unwrap();
+            prop_assert_eq!(a * b, x * y);
+            let (a, b, c) = get_triples_from_numbers(&numbers).unwrap();
+            let (x, y, z) = naive::get_triples_from_numbers(&numbers).unwrap();
+            prop_assert_eq!(a * b * c, x * y * z);
+        }
+    }
 }
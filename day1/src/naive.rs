@@ -0,0 +1,20 @@
+// The original implementations, trying every pair and triple in turn.
+// Kept around as a reference for the property tests against the hashing versions.
+use crate::TOTAL;
+use itertools::Itertools;
+
+pub fn get_pairs_from_numbers(numbers: &[i32]) -> Option<(i32, i32)> {
+    numbers
+        .iter()
+        .copied()
+        .tuple_combinations()
+        .find(|(a, b)| *a + *b == TOTAL)
+}
+
+pub fn get_triples_from_numbers(numbers: &[i32]) -> Option<(i32, i32, i32)> {
+    numbers
+        .iter()
+        .copied()
+        .tuple_combinations()
+        .find(|(a, b, c)| *a + *b + *c == TOTAL)
+}
@@ -0,0 +1,342 @@
+// Beyond the single earliest answer: every time that works, and the closest
+// we can get when no time works for every bus.
+use crate::{BusSchedule, ScheduleError};
+use std::convert::TryFrom;
+
+/// Every time a schedule's buses all line up, in order. The times for each
+/// combination of offsets repeat every `lcm(frequencies)` minutes, and with
+/// constraints there can be several of those sequences to merge together.
+pub struct ValidTimes {
+    /// (next time, repeat) for each sequence, `None` once it's past the end
+    sequences: Vec<(Option<u128>, u128)>,
+    end: Option<u128>,
+}
+
+impl Iterator for ValidTimes {
+    type Item = u128;
+
+    fn next(&mut self) -> Option<u128> {
+        let time = self.sequences.iter().filter_map(|(next, _)| *next).min()?;
+        if self.end.is_some_and(|end| time > end) {
+            self.sequences.clear();
+            return None;
+        }
+        for (next, repeat) in self.sequences.iter_mut() {
+            if *next == Some(time) {
+                *next = time.checked_add(*repeat);
+            }
+        }
+        Some(time)
+    }
+}
+
+/// The valid times from `start` onwards, up to and including `end` if given
+pub fn valid_times(
+    schedule: &BusSchedule,
+    start: u128,
+    end: Option<u128>,
+) -> Result<ValidTimes, ScheduleError> {
+    let sequences = schedule
+        .solutions()?
+        .into_iter()
+        .map(|(first, repeat)| {
+            let next = if start <= first {
+                Some(first)
+            } else {
+                (start - first)
+                    .div_ceil(repeat)
+                    .checked_mul(repeat)
+                    .and_then(|repeats| repeats.checked_add(first))
+            };
+            (next, repeat)
+        })
+        .collect();
+    Ok(ValidTimes { sequences, end })
+}
+
+/// The earliest time where as many buses as possible leave at their offsets
+#[derive(Debug, PartialEq)]
+pub struct PartialAlignment {
+    pub time: u128,
+    /// The ids of the buses that do leave on time
+    pub buses: Vec<u128>,
+}
+
+/// How many combinations of offsets `best_partial_alignment` tries before
+/// giving up. Each group of buses counts as at least one.
+pub const SEARCH_LIMIT: usize = 100_000;
+
+/// When no time works for every bus, find the largest group of buses that
+/// can line up, and the earliest time they do. If several groups of the same
+/// size work, the one with the earliest time wins. A constraint after a bus
+/// that's been dropped no longer applies, and a bus left with nothing to line
+/// up with doesn't count towards a group. Fails if any group overflows, as
+/// we can't tell whether it would have lined up.
+/// This tries every group of buses, largest first, and every combination of
+/// offsets in each, so it stops with `ScheduleError::SearchLimit` after
+/// `SEARCH_LIMIT` combinations rather than running for ever.
+pub fn best_partial_alignment(schedule: &BusSchedule) -> Result<PartialAlignment, ScheduleError> {
+    best_partial_alignment_within(schedule, SEARCH_LIMIT)
+}
+
+// `best_partial_alignment`, giving up after `limit` combinations of offsets
+fn best_partial_alignment_within(
+    schedule: &BusSchedule,
+    limit: usize,
+) -> Result<PartialAlignment, ScheduleError> {
+    // Buses with no offset or constraint never have to line up with anything
+    let candidates: Vec<usize> = (0..schedule.buses.len())
+        .filter(|i| {
+            let bus = &schedule.buses[*i];
+            bus.arrival.is_some() || schedule.constraints.iter().any(|c| c.bus == bus.id)
+        })
+        .collect();
+    let mut search = Search {
+        schedule,
+        candidates: &candidates,
+        tried: 0,
+        limit,
+    };
+    for size in (1..=candidates.len()).rev() {
+        let mut best: Option<PartialAlignment> = None;
+        let mut chosen = Vec::<usize>::new();
+        search.try_groups(size, 0, &mut chosen, &mut best)?;
+        if let Some(best) = best {
+            return Ok(best);
+        }
+    }
+    Err(ScheduleError::NoBuses)
+}
+
+// The buses `best_partial_alignment` picks groups from, and how much of
+// its limit it has used
+struct Search<'a> {
+    schedule: &'a BusSchedule,
+    candidates: &'a [usize],
+    tried: usize,
+    limit: usize,
+}
+
+impl Search<'_> {
+    // Count `combinations` more towards the limit, failing if that goes over
+    fn spend(&mut self, combinations: Option<usize>) -> Result<(), ScheduleError> {
+        match combinations.and_then(|combinations| self.tried.checked_add(combinations)) {
+            Some(tried) if tried <= self.limit => {
+                self.tried = tried;
+                Ok(())
+            }
+            _ => Err(ScheduleError::SearchLimit(self.tried)),
+        }
+    }
+
+    // Try every group of `size` buses from `candidates[from..]` added to `chosen`
+    fn try_groups(
+        &mut self,
+        size: usize,
+        from: usize,
+        chosen: &mut Vec<usize>,
+        best: &mut Option<PartialAlignment>,
+    ) -> Result<(), ScheduleError> {
+        if chosen.len() == size {
+            return self.try_group(size, chosen, best);
+        }
+        for i in from..self.candidates.len() {
+            chosen.push(i);
+            self.try_groups(size, i + 1, chosen, best)?;
+            chosen.pop();
+        }
+        Ok(())
+    }
+
+    fn try_group(
+        &mut self,
+        size: usize,
+        chosen: &[usize],
+        best: &mut Option<PartialAlignment>,
+    ) -> Result<(), ScheduleError> {
+        let schedule = self.schedule;
+        let mut included = vec![false; schedule.buses.len()];
+        for i in chosen.iter() {
+            included[self.candidates[*i]] = true;
+        }
+        let group = match schedule.offset_ranges_for(&included) {
+            Ok(group) if group.len() == size => group,
+            Err(err @ ScheduleError::Overflow { .. }) => return Err(err),
+            _ => return self.spend(Some(1)),
+        };
+        // One CRT per combination of offsets in the group's windows
+        let combinations = group.iter().try_fold(1_usize, |total, (_, low, high)| {
+            usize::try_from(high - low + 1)
+                .ok()
+                .and_then(|width| total.checked_mul(width))
+        });
+        self.spend(combinations.map(|combinations| combinations.max(1)))?;
+        let earliest = match schedule.solutions_for(&group) {
+            Ok(solutions) => solutions.iter().map(|(time, _)| *time).min(),
+            Err(err @ ScheduleError::Overflow { .. }) => return Err(err),
+            Err(_) => None,
+        };
+        if let Some(time) = earliest {
+            if best.as_ref().is_none_or(|best| time < best.time) {
+                *best = Some(PartialAlignment {
+                    time,
+                    buses: group
+                        .iter()
+                        .map(|(i, _, _)| schedule.buses[*i].id)
+                        .collect(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::convert::TryFrom;
+
+    fn parse_schedule(text: &str) -> BusSchedule {
+        BusSchedule::try_from(&text.lines().collect::<Vec<&str>>()[..]).unwrap()
+    }
+
+    #[test]
+    fn test_valid_times() {
+        let schedule = parse_schedule("0\n17,x,13,19");
+        let times: Vec<u128> = valid_times(&schedule, 0, None).unwrap().take(3).collect();
+        let repeat = 17 * 13 * 19;
+        assert_eq!(times, [3417, 3417 + repeat, 3417 + 2 * repeat]);
+        let times: Vec<u128> = valid_times(&schedule, 3418, Some(3417 + 3 * repeat))
+            .unwrap()
+            .collect();
+        assert_eq!(times, [3417 + repeat, 3417 + 2 * repeat, 3417 + 3 * repeat]);
+        assert_eq!(valid_times(&schedule, 0, Some(3416)).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_valid_times_with_constraints() {
+        // Bus 5 can leave 0 or 1 minutes after bus 4, which has to line up with bus 3
+        let schedule = parse_schedule(
+            "bus 4 offset 0\n\
+             bus 3 offset 0\n\
+             bus 5\n\
+             constraint 5 within 1 after 4",
+        );
+        let times: Vec<u128> = valid_times(&schedule, 0, Some(130)).unwrap().collect();
+        // t = 0 mod 12, and t or t + 1 a multiple of 5
+        assert_eq!(times, [0, 24, 60, 84, 120]);
+    }
+
+    #[test]
+    fn test_valid_times_overflow() {
+        let schedule = parse_schedule("0\n7");
+        let times: Vec<u128> = valid_times(&schedule, u128::MAX - 20, None)
+            .unwrap()
+            .collect();
+        assert_eq!(times.len(), 3);
+    }
+
+    #[test]
+    fn test_best_partial_alignment() {
+        // 4 and 6 can never line up a minute apart, so one of them has to go.
+        // Without 6 the answer is 12, without 4 it's 17.
+        let schedule = parse_schedule("0\n4,6,x,5");
+        assert!(schedule.calculate_time().is_err());
+        assert_eq!(
+            best_partial_alignment(&schedule),
+            Ok(PartialAlignment {
+                time: 12,
+                buses: vec![4, 5]
+            })
+        );
+        let schedule = parse_schedule("939\n7,13,x,x,59,x,31,19");
+        assert_eq!(
+            best_partial_alignment(&schedule).unwrap().time,
+            schedule.calculate_time().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_best_partial_alignment_drops_constraints() {
+        // 4 and 6 can't line up. Keeping 4 and 5 would leave 5 with nothing
+        // to be within 2 minutes of, so the best is 6 and 5.
+        let schedule = parse_schedule(
+            "bus 4 offset 0\n\
+             bus 6 offset 1\n\
+             bus 5\n\
+             constraint 5 within 2 after 6",
+        );
+        assert!(schedule.calculate_time().is_err());
+        assert_eq!(
+            best_partial_alignment(&schedule),
+            Ok(PartialAlignment {
+                time: 17,
+                buses: vec![6, 5]
+            })
+        );
+        // Bus 3 can't be within a minute of both, so drop a bus to fix it
+        let schedule = parse_schedule(
+            "bus 4 offset 0\n\
+             bus 5 offset 10\n\
+             bus 3\n\
+             constraint 3 within 1 after 4\n\
+             constraint 3 within 1 after 5",
+        );
+        assert_eq!(
+            schedule.calculate_time(),
+            Err(ScheduleError::ConflictingConstraints(3))
+        );
+        assert_eq!(best_partial_alignment(&schedule).unwrap().buses.len(), 2);
+    }
+
+    #[test]
+    fn test_search_limit() {
+        let schedule = parse_schedule(
+            "bus 4 offset 0\n\
+             bus 6 offset 1\n\
+             bus 5\n\
+             constraint 5 within 2 after 6",
+        );
+        // All three buses have 3 combinations, then 4 and 6 one more
+        assert_eq!(
+            best_partial_alignment_within(&schedule, 3),
+            Err(ScheduleError::SearchLimit(3))
+        );
+        assert_eq!(
+            best_partial_alignment_within(&schedule, 20).unwrap().buses,
+            vec![6, 5]
+        );
+        // A window wider than the limit gives up straight away
+        let schedule = parse_schedule(
+            "bus 4 offset 0\n\
+             bus 6 offset 1\n\
+             bus 1000003\n\
+             constraint 1000003 within 1000000 after 6",
+        );
+        assert_eq!(
+            best_partial_alignment(&schedule),
+            Err(ScheduleError::SearchLimit(0))
+        );
+    }
+
+    #[test]
+    fn test_overflow_is_not_dropped() {
+        let schedule = parse_schedule(
+            "bus 1000000007 offset 0\n\
+             bus 998244353 offset 1\n\
+             bus 1000000009 offset 2\n\
+             bus 1000000021 offset 3\n\
+             bus 1000000033 offset 4\n\
+             bus 4 offset 0\n\
+             bus 6 offset 1",
+        );
+        assert!(matches!(
+            best_partial_alignment(&schedule),
+            Err(ScheduleError::Overflow { .. })
+        ));
+        assert!(matches!(
+            valid_times(&schedule, 0, None),
+            Err(ScheduleError::Overflow { .. })
+        ));
+    }
+}
@@ -0,0 +1,180 @@
+// The Chinese Remainder Theorem, generalised so the moduli don't have to be
+// coprime. Each bus gives a rule "t mod frequency = remainder", and we merge
+// the rules two at a time into a single rule that satisfies both.
+
+/// Every time `t` where `t mod modulus == remainder`
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Congruence {
+    pub remainder: u128,
+    pub modulus: u128,
+}
+
+impl Congruence {
+    pub fn new(remainder: u128, modulus: u128) -> Congruence {
+        Congruence {
+            remainder: remainder % modulus,
+            modulus,
+        }
+    }
+}
+
+/// Why two congruences couldn't be combined
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum CrtError {
+    /// No time satisfies both (only possible when the moduli share a factor)
+    NoSolution,
+    /// The combined modulus doesn't fit in a u128
+    Overflow,
+}
+
+/// Extended Euclid, returning `(g, x, y)` where `g = gcd(a, b)` and `a*x + b*y = g`
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        return (a, 1, 0);
+    }
+    let (g, x, y) = extended_gcd(b, a % b);
+    (g, y, x - (a / b) * y)
+}
+
+// `a * b mod modulus` without overflowing, for a modulus up to 2^127
+fn mul_mod(a: u128, b: u128, modulus: u128) -> u128 {
+    if let Some(product) = a.checked_mul(b) {
+        return product % modulus;
+    }
+    // Double and add, so nothing gets bigger than 2 * modulus
+    let (mut a, mut b, mut ret) = (a % modulus, b, 0);
+    while b > 0 {
+        if b & 1 == 1 {
+            ret = (ret + a) % modulus;
+        }
+        a = (a + a) % modulus;
+        b >>= 1;
+    }
+    ret
+}
+
+fn gcd(a: u128, b: u128) -> u128 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// The single congruence that holds exactly when both `a` and `b` hold
+pub fn combine(a: Congruence, b: Congruence) -> Result<Congruence, CrtError> {
+    let g = gcd(a.modulus, b.modulus);
+    let (low, high) = if a.remainder < b.remainder {
+        (a.remainder, b.remainder)
+    } else {
+        (b.remainder, a.remainder)
+    };
+    if (high - low) % g != 0 {
+        return Err(CrtError::NoSolution);
+    }
+    let modulus = (a.modulus / g)
+        .checked_mul(b.modulus)
+        .ok_or(CrtError::Overflow)?;
+    // a.remainder + a.modulus * k works for b when
+    // (a.modulus / g) * k = (b.remainder - a.remainder) / g  (mod b.modulus / g)
+    let step = b.modulus / g;
+    if step > i128::MAX as u128 {
+        return Err(CrtError::Overflow);
+    }
+    let (_, inverse, _) = extended_gcd((a.modulus / g % step) as i128, step as i128);
+    let inverse = inverse.rem_euclid(step as i128) as u128;
+    let difference = (high - low) / g % step;
+    let difference = if a.remainder <= b.remainder {
+        difference
+    } else {
+        (step - difference) % step
+    };
+    let k = mul_mod(difference, inverse, step);
+    // Both of these stay below `modulus`, which we know fits
+    Ok(Congruence::new(a.remainder + a.modulus * k, modulus))
+}
+
+/// Merge every congruence into one. The smallest time satisfying all of them is
+/// its `remainder`. Fails with the index of the first congruence that can't be
+/// combined with the ones before it, and why.
+pub fn solve(congruences: &[Congruence]) -> Result<Congruence, (usize, CrtError)> {
+    let mut solution = Congruence::new(0, 1);
+    for (i, congruence) in congruences.iter().enumerate() {
+        solution = combine(solution, *congruence).map_err(|err| (i, err))?;
+    }
+    Ok(solution)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_extended_gcd() {
+        let (g, x, y) = extended_gcd(240, 46);
+        assert_eq!(g, 2);
+        assert_eq!(240 * x + 46 * y, 2);
+    }
+
+    #[test]
+    fn test_combine_coprime() {
+        // t = 2 mod 3, t = 3 mod 5 -> t = 8 mod 15
+        assert_eq!(
+            combine(Congruence::new(2, 3), Congruence::new(3, 5)),
+            Ok(Congruence::new(8, 15))
+        );
+    }
+
+    #[test]
+    fn test_combine_shared_factor() {
+        // t = 0 mod 4, t = 4 mod 6 -> t = 4 mod 12
+        assert_eq!(
+            combine(Congruence::new(0, 4), Congruence::new(4, 6)),
+            Ok(Congruence::new(4, 12))
+        );
+        // t even and t odd
+        assert_eq!(
+            combine(Congruence::new(0, 4), Congruence::new(5, 6)),
+            Err(CrtError::NoSolution)
+        );
+    }
+
+    #[test]
+    fn test_solve() {
+        let congruences = [
+            Congruence::new(0, 3),
+            Congruence::new(3, 4),
+            Congruence::new(4, 5),
+        ];
+        assert_eq!(solve(&congruences), Ok(Congruence::new(39, 60)));
+        assert_eq!(solve(&[]), Ok(Congruence::new(0, 1)));
+        let conflicting = [
+            Congruence::new(1, 2),
+            Congruence::new(0, 3),
+            Congruence::new(2, 4),
+        ];
+        assert_eq!(solve(&conflicting), Err((2, CrtError::NoSolution)));
+    }
+
+    #[test]
+    fn test_mul_mod() {
+        let modulus = (1u128 << 127) - 1;
+        assert_eq!(mul_mod(modulus - 1, modulus - 1, modulus), 1);
+        assert_eq!(mul_mod(6, 7, 5), 2);
+    }
+
+    #[test]
+    fn test_large_moduli() {
+        // Two primes just under 2^64, so the combined modulus only fits in a u128
+        let p = 18446744073709551557u128;
+        let q = 18446744073709551533u128;
+        let combined = combine(Congruence::new(p - 1, p), Congruence::new(5, q)).unwrap();
+        assert_eq!(combined.modulus, p * q);
+        assert_eq!(combined.remainder % p, p - 1);
+        assert_eq!(combined.remainder % q, 5);
+        assert_eq!(
+            combine(combined, Congruence::new(1, 1 << 100)),
+            Err(CrtError::Overflow)
+        );
+    }
+}
@@ -0,0 +1,196 @@
+// A more flexible schedule format than the puzzle's, with one line per bus
+// or constraint so each bus can have its own frequency, phase and offset:
+//
+// # comments and blank lines are ignored
+// start 939
+// bus 7 offset 0
+// bus 13 every 15 phase 4 offset 1
+// bus 59
+// constraint 59 within 5 after 13
+//
+// `bus ID` can be followed by any of `every FREQUENCY` (defaults to the id),
+// `phase FIRST_DEPARTURE` (defaults to 0) and `offset MINUTES`, each at most
+// once. A bus without an offset doesn't have to leave at any particular time,
+// unless a constraint says it has to leave at most so many minutes after a
+// bus that does.
+use crate::{Bus, BusSchedule, Constraint, ScheduleError};
+
+/// Whether the lines are in this format rather than the puzzle's
+pub fn is_explicit(lines: &[&str]) -> bool {
+    lines
+        .iter()
+        .map(|line| line.trim())
+        .find(|line| !line.is_empty() && !line.starts_with('#'))
+        .and_then(|line| line.split_whitespace().next())
+        .is_some_and(|word| matches!(word, "start" | "bus" | "constraint"))
+}
+
+pub fn parse(lines: &[&str]) -> Result<BusSchedule, ScheduleError> {
+    let mut schedule = BusSchedule {
+        start_time: 0,
+        buses: vec![],
+        constraints: vec![],
+    };
+    for (i, line) in lines.iter().enumerate() {
+        let invalid = || ScheduleError::InvalidLine {
+            line: i + 1,
+            text: String::from(*line),
+        };
+        let number = |word: &str| word.parse::<u128>().map_err(|_| invalid());
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            [] => (),
+            [first, ..] if first.starts_with('#') => (),
+            ["start", time] => schedule.start_time = number(time)?,
+            ["bus", id, options @ ..] => {
+                let id = number(id)?;
+                let mut bus = Bus {
+                    id,
+                    frequency: id,
+                    phase: 0,
+                    arrival: None,
+                };
+                let mut seen = Vec::<&str>::new();
+                for option in options.chunks(2) {
+                    if seen.contains(&option[0]) {
+                        return Err(ScheduleError::DuplicateOption {
+                            line: i + 1,
+                            option: String::from(option[0]),
+                        });
+                    }
+                    seen.push(option[0]);
+                    match option {
+                        ["every", frequency] => bus.frequency = number(frequency)?,
+                        ["phase", phase] => bus.phase = number(phase)?,
+                        ["offset", arrival] => bus.arrival = Some(number(arrival)?),
+                        _ => return Err(invalid()),
+                    }
+                }
+                if bus.frequency == 0 {
+                    return Err(ScheduleError::ZeroFrequencyBus {
+                        line: i + 1,
+                        bus: id,
+                    });
+                }
+                if schedule.bus(id).is_some() {
+                    return Err(ScheduleError::DuplicateBus(id));
+                }
+                schedule.buses.push(bus);
+            }
+            ["constraint", bus, "within", within, "after", after] => {
+                schedule.constraints.push(Constraint {
+                    bus: number(bus)?,
+                    after: number(after)?,
+                    within: number(within)?,
+                })
+            }
+            _ => return Err(invalid()),
+        }
+    }
+    for constraint in schedule.constraints.iter() {
+        for id in [constraint.bus, constraint.after] {
+            if schedule.bus(id).is_none() {
+                return Err(ScheduleError::UnknownBus(id));
+            }
+        }
+        if schedule.bus(constraint.after).unwrap().arrival.is_none() {
+            return Err(ScheduleError::InvalidConstraint {
+                bus: constraint.bus,
+                after: constraint.after,
+            });
+        }
+    }
+    Ok(schedule)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_is_explicit() {
+        assert!(is_explicit(&["# a comment", "", "start 939"]));
+        assert!(is_explicit(&["bus 7 offset 0"]));
+        assert!(!is_explicit(&["939", "7,13,x,x,59,x,31,19"]));
+        assert!(!is_explicit(&[]));
+    }
+
+    #[test]
+    fn test_parse() {
+        let schedule = parse(
+            &include_str!("../test_data_explicit.txt")
+                .lines()
+                .collect::<Vec<&str>>(),
+        )
+        .unwrap();
+        assert_eq!(schedule.start_time, 939);
+        assert_eq!(schedule.buses.len(), 3);
+        let bus = schedule.bus(7).unwrap();
+        assert_eq!((bus.frequency, bus.phase, bus.arrival), (7, 3, None));
+        let bus = schedule.bus(4).unwrap();
+        assert_eq!((bus.frequency, bus.phase, bus.arrival), (6, 0, Some(0)));
+        assert_eq!(
+            schedule.constraints,
+            [Constraint {
+                bus: 7,
+                after: 4,
+                within: 2
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for (lines, expected) in [
+            (
+                &["bus 7 every"][..],
+                ScheduleError::InvalidLine {
+                    line: 1,
+                    text: String::from("bus 7 every"),
+                },
+            ),
+            (
+                &["start 1", "bus seven"][..],
+                ScheduleError::InvalidLine {
+                    line: 2,
+                    text: String::from("bus seven"),
+                },
+            ),
+            (&["bus 7", "bus 7"][..], ScheduleError::DuplicateBus(7)),
+            (
+                &["bus 7", "bus 3 every 0"][..],
+                ScheduleError::ZeroFrequencyBus { line: 2, bus: 3 },
+            ),
+            (
+                &["bus 0"][..],
+                ScheduleError::ZeroFrequencyBus { line: 1, bus: 0 },
+            ),
+            (
+                &["bus 7 offset 0 offset 3"][..],
+                ScheduleError::DuplicateOption {
+                    line: 1,
+                    option: String::from("offset"),
+                },
+            ),
+            (
+                &["start 1", "bus 7 every 3 phase 1 every 3"][..],
+                ScheduleError::DuplicateOption {
+                    line: 2,
+                    option: String::from("every"),
+                },
+            ),
+            (
+                &["bus 7 offset 0", "constraint 9 within 2 after 7"][..],
+                ScheduleError::UnknownBus(9),
+            ),
+            (
+                &["bus 7", "bus 9", "constraint 9 within 2 after 7"][..],
+                ScheduleError::InvalidConstraint { bus: 9, after: 7 },
+            ),
+        ]
+        .iter()
+        {
+            assert_eq!(parse(lines).unwrap_err(), *expected);
+        }
+    }
+}
@@ -1,43 +1,601 @@
+mod alignment;
+mod crt;
+mod explicit;
+mod simulation;
+
+use crt::{Congruence, CrtError};
+use harness::{AnswerCheck, Mode, Timings};
+use simulation::SimulationStart;
+use std::convert::TryFrom;
+
 #[derive(Debug)]
 struct Bus {
-    arrival: u32,
-    frequency: u32,
+    /// In the puzzle's format the id is also the frequency
+    id: u128,
+    frequency: u128,
+    /// The first time the bus leaves, after which it leaves every `frequency` minutes
+    phase: u128,
+    /// How many minutes after the day b time the bus must leave, if it has to
+    arrival: Option<u128>,
+}
+
+/// Bus `bus` has to leave at most `within` minutes after bus `after` does
+#[derive(Debug, PartialEq)]
+struct Constraint {
+    bus: u128,
+    after: u128,
+    within: u128,
 }
 
 #[derive(Debug)]
 struct BusSchedule {
-    start_time: u32,
+    start_time: u128,
     buses: Vec<Bus>,
+    constraints: Vec<Constraint>,
 }
-impl From<&[&str]> for BusSchedule {
-    fn from(lines: &[&str]) -> BusSchedule {
+
+impl std::convert::TryFrom<&[&str]> for BusSchedule {
+    type Error = ScheduleError;
+
+    // Either the puzzle's two line format, or the explicit format in `explicit.rs`
+    fn try_from(lines: &[&str]) -> Result<BusSchedule, ScheduleError> {
+        if explicit::is_explicit(lines) {
+            return explicit::parse(lines);
+        }
+        if lines.len() < 2 {
+            return Err(ScheduleError::MissingLine);
+        }
         let start_time = lines[0]
-            .parse::<u32>()
-            .expect("First line should be an integer");
+            .trim()
+            .parse::<u128>()
+            .map_err(|_| ScheduleError::InvalidStartTime(String::from(lines[0])))?;
         let mut buses = Vec::<Bus>::new();
 
-        for (i, value) in lines[1].split(',').enumerate() {
-            if value != "x" {
+        let frequencies = parsing::separated_with_placeholder::<u128>(lines[1].trim(), ',', "x")
+            .map_err(|err| match err {
+                parsing::ParseError::Value { index, text, .. } => ScheduleError::InvalidFrequency {
+                    position: index,
+                    value: text,
+                },
+                _ => unreachable!("Only values are parsed from a list"),
+            })?;
+        for (i, frequency) in frequencies.into_iter().enumerate() {
+            if let Some(frequency) = frequency {
+                if frequency == 0 {
+                    return Err(ScheduleError::ZeroFrequency { position: i });
+                }
                 buses.push(Bus {
-                    arrival: i as u32,
-                    frequency: value.parse().expect("Values must be integers"),
+                    id: frequency,
+                    frequency,
+                    phase: 0,
+                    arrival: Some(i as u128),
                 })
             }
         }
-        BusSchedule { start_time, buses }
+        Ok(BusSchedule {
+            start_time,
+            buses,
+            constraints: vec![],
+        })
+    }
+}
+
+/// A bus leaving at `time`, `wait` minutes after the schedule's start time
+#[derive(Debug, PartialEq)]
+struct Departure {
+    bus: u128,
+    time: u128,
+    wait: u128,
+}
+
+#[derive(Debug, PartialEq)]
+enum ScheduleError {
+    /// The input needs a start time line and a bus line
+    MissingLine,
+    InvalidStartTime(String),
+    /// A bus (0-indexed position in the list) that isn't `x` or a number that fits in a u128
+    InvalidFrequency {
+        position: usize,
+        value: String,
+    },
+    /// A bus that never leaves
+    ZeroFrequency {
+        position: usize,
+    },
+    /// A bus in the explicit format (1-indexed line) that never leaves
+    ZeroFrequencyBus {
+        line: usize,
+        bus: u128,
+    },
+    /// A bus option in the explicit format (1-indexed line) given more than once
+    DuplicateOption {
+        line: usize,
+        option: String,
+    },
+    /// A line of the explicit format that couldn't be understood (1-indexed line)
+    InvalidLine {
+        line: usize,
+        text: String,
+    },
+    DuplicateBus(u128),
+    /// A constraint naming a bus that isn't in the schedule
+    UnknownBus(u128),
+    /// A constraint relative to a bus without a fixed offset
+    InvalidConstraint {
+        bus: u128,
+        after: u128,
+    },
+    /// Constraints on the bus that can't all be true at once
+    ConflictingConstraints(u128),
+    NoBuses,
+    /// No time works for this bus leaving at this offset, along with the buses before it
+    NoSolution {
+        bus: u128,
+        arrival: u128,
+    },
+    /// A time too big for a u128, caused by this bus
+    Overflow {
+        bus: u128,
+    },
+    /// A search that stopped after trying this many combinations of offsets
+    SearchLimit(usize),
+}
+
+impl std::fmt::Display for ScheduleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScheduleError::MissingLine => write!(f, "expected a start time line and a bus line"),
+            ScheduleError::InvalidStartTime(value) => {
+                write!(f, "start time {:?} is not a number", value)
+            }
+            ScheduleError::InvalidFrequency { position, value } => write!(
+                f,
+                "bus {:?} at position {} is not a number or x",
+                value, position
+            ),
+            ScheduleError::ZeroFrequency { position } => {
+                write!(f, "bus at position {} has a frequency of 0", position)
+            }
+            ScheduleError::ZeroFrequencyBus { line, bus } => {
+                write!(f, "bus {} on line {} has a frequency of 0", bus, line)
+            }
+            ScheduleError::DuplicateOption { line, option } => {
+                write!(f, "line {} gives {:?} more than once", line, option)
+            }
+            ScheduleError::InvalidLine { line, text } => {
+                write!(f, "line {} is not understood: {:?}", line, text)
+            }
+            ScheduleError::DuplicateBus(bus) => write!(f, "bus {} is listed twice", bus),
+            ScheduleError::UnknownBus(bus) => write!(f, "there is no bus {}", bus),
+            ScheduleError::InvalidConstraint { bus, after } => write!(
+                f,
+                "bus {} can't be constrained by bus {} as that has no offset",
+                bus, after
+            ),
+            ScheduleError::ConflictingConstraints(bus) => {
+                write!(f, "the constraints on bus {} conflict", bus)
+            }
+            ScheduleError::NoBuses => write!(f, "there are no buses"),
+            ScheduleError::NoSolution { bus, arrival } => write!(
+                f,
+                "no time works for bus {} at offset {} along with the buses before it",
+                bus, arrival
+            ),
+            ScheduleError::Overflow { bus } => {
+                write!(f, "times for bus {} are too big for a u128", bus)
+            }
+            ScheduleError::SearchLimit(tried) => write!(
+                f,
+                "search limit reached after {} combinations of offsets",
+                tried
+            ),
+        }
+    }
+}
+
+impl Bus {
+    // The first time at or after `time` that this bus leaves
+    fn next_departure(&self, time: u128) -> Result<u128, ScheduleError> {
+        if time <= self.phase {
+            return Ok(self.phase);
+        }
+        (time - self.phase)
+            .div_ceil(self.frequency)
+            .checked_mul(self.frequency)
+            .and_then(|since_phase| since_phase.checked_add(self.phase))
+            .ok_or(ScheduleError::Overflow { bus: self.id })
+    }
+
+    fn departs_at(&self, time: u128) -> bool {
+        time >= self.phase && (time - self.phase).is_multiple_of(self.frequency)
+    }
+
+    // The times `t` where this bus leaves `arrival` minutes after `t`
+    // (ignoring that it doesn't leave before its phase)
+    fn congruence(&self, arrival: u128) -> Congruence {
+        let phase = self.phase % self.frequency;
+        let arrival = arrival % self.frequency;
+        let remainder = if phase >= arrival {
+            phase - arrival
+        } else {
+            phase + (self.frequency - arrival)
+        };
+        Congruence::new(remainder, self.frequency)
     }
 }
 
 impl BusSchedule {
-    fn calculate_time(&self) -> u64 {
+    fn bus(&self, id: u128) -> Option<&Bus> {
+        self.buses.iter().find(|bus| bus.id == id)
+    }
+
+    /// The next departure of every bus at or after the start time, soonest first
+    fn upcoming_departures(&self) -> Result<Vec<Departure>, ScheduleError> {
+        let mut departures = Vec::<Departure>::new();
+        for bus in self.buses.iter() {
+            let time = bus.next_departure(self.start_time)?;
+            departures.push(Departure {
+                bus: bus.id,
+                time,
+                wait: time - self.start_time,
+            });
+        }
+        departures.sort_by_key(|departure| (departure.time, departure.bus));
+        Ok(departures)
+    }
+
+    /// The first bus we can catch
+    fn earliest_departure(&self) -> Result<Departure, ScheduleError> {
+        self.upcoming_departures()?
+            .into_iter()
+            .next()
+            .ok_or(ScheduleError::NoBuses)
+    }
+
+    // Day a, the earliest bus's id multiplied by how long we wait for it
+    fn calculate_day_a(&self) -> Result<u128, ScheduleError> {
+        let departure = self.earliest_departure()?;
+        departure
+            .bus
+            .checked_mul(departure.wait)
+            .ok_or(ScheduleError::Overflow { bus: departure.bus })
+    }
+
+    /// The next `count` departures of each bus from the start time, one line per bus, e.g.
+    /// `bus 7: 945 952 959`
+    fn timetable(&self, count: usize) -> Result<String, ScheduleError> {
+        let mut ret = String::new();
+        for bus in self.buses.iter() {
+            let mut time = bus.next_departure(self.start_time)?;
+            let mut times = Vec::<String>::new();
+            for i in 0..count {
+                if i > 0 {
+                    time = time
+                        .checked_add(bus.frequency)
+                        .ok_or(ScheduleError::Overflow { bus: bus.id })?;
+                }
+                times.push(time.to_string());
+            }
+            ret += &format!("bus {}: {}\n", bus.id, times.join(" "));
+        }
+        Ok(ret)
+    }
+
+    /// How many minutes after the day b time the last bus with an offset or
+    /// constraint could leave
+    fn span(&self) -> u128 {
+        let fixed = self.buses.iter().filter_map(|bus| bus.arrival);
+        let constrained = self.constraints.iter().filter_map(|constraint| {
+            self.bus(constraint.after)
+                .and_then(|after| after.arrival)
+                .and_then(|arrival| arrival.checked_add(constraint.within))
+        });
+        fixed.chain(constrained).max().unwrap_or(0)
+    }
+
+    // For every bus that has to leave at some offset, the (lowest, highest)
+    // offset worth trying, taking the constraints into account. Offsets a
+    // whole `frequency` apart give the same congruence, so a window wider than
+    // that is cut down to its last `frequency` offsets (the latest offset for
+    // each remainder is the one least held back by the bus's phase).
+    fn offset_ranges(&self) -> Result<Vec<(usize, u128, u128)>, ScheduleError> {
+        self.offset_ranges_for(&vec![true; self.buses.len()])
+    }
+
+    // `offset_ranges` for just the included buses (by index). Constraints
+    // after a bus that isn't included are ignored, so a bus only held by
+    // those doesn't have to leave at any offset.
+    fn offset_ranges_for(
+        &self,
+        included: &[bool],
+    ) -> Result<Vec<(usize, u128, u128)>, ScheduleError> {
+        let mut ranges = Vec::<(usize, u128, u128)>::new();
+        for (i, bus) in self.buses.iter().enumerate() {
+            if !included[i] {
+                continue;
+            }
+            let mut range = bus.arrival.map(|arrival| (arrival, arrival));
+            for constraint in self.constraints.iter().filter(|c| {
+                c.bus == bus.id
+                    && self
+                        .buses
+                        .iter()
+                        .position(|after| after.id == c.after)
+                        .is_none_or(|after| included[after])
+            }) {
+                let after = self
+                    .bus(constraint.after)
+                    .and_then(|after| after.arrival)
+                    .ok_or(ScheduleError::InvalidConstraint {
+                        bus: bus.id,
+                        after: constraint.after,
+                    })?;
+                let latest = after
+                    .checked_add(constraint.within)
+                    .ok_or(ScheduleError::Overflow { bus: bus.id })?;
+                range = Some(match range {
+                    Some((low, high)) => (low.max(after), high.min(latest)),
+                    None => (after, latest),
+                });
+            }
+            match range {
+                Some((low, high)) if low > high => {
+                    return Err(ScheduleError::ConflictingConstraints(bus.id))
+                }
+                Some((low, high)) => {
+                    let low = low.max(high - (bus.frequency - 1).min(high));
+                    ranges.push((i, low, high))
+                }
+                None => (),
+            }
+        }
+        Ok(ranges)
+    }
+
+    // The earliest time where each bus leaves at the given offset (bus index, offset),
+    // and how often that repeats
+    fn solve_offsets(&self, offsets: &[(usize, u128)]) -> Result<(u128, u128), ScheduleError> {
+        let congruences: Vec<Congruence> = offsets
+            .iter()
+            .map(|(i, arrival)| self.buses[*i].congruence(*arrival))
+            .collect();
+        let solution = match crt::solve(&congruences) {
+            Ok(solution) => solution,
+            Err((i, CrtError::NoSolution)) => {
+                return Err(ScheduleError::NoSolution {
+                    bus: self.buses[offsets[i].0].id,
+                    arrival: offsets[i].1,
+                })
+            }
+            Err((i, CrtError::Overflow)) => {
+                return Err(ScheduleError::Overflow {
+                    bus: self.buses[offsets[i].0].id,
+                })
+            }
+        };
+        // Buses don't leave before their phase, so the answer might be a later repeat
+        let mut time = solution.remainder;
+        for (i, arrival) in offsets.iter() {
+            let earliest = self.buses[*i].phase.saturating_sub(*arrival);
+            if time < earliest {
+                time = (earliest - time)
+                    .div_ceil(solution.modulus)
+                    .checked_mul(solution.modulus)
+                    .and_then(|repeats| repeats.checked_add(time))
+                    .ok_or(ScheduleError::Overflow {
+                        bus: self.buses[*i].id,
+                    })?;
+            }
+        }
+        Ok((time, solution.modulus))
+    }
+
+    // Every (earliest time, repeat) that works with the buses leaving at offsets
+    // within the given ranges. A bus with a constraint could leave at several
+    // offsets, so we try every combination and keep all of the ones that work.
+    // Fails with the first combination's problem if none of them work, and
+    // straight away if any combination overflows, as the times it would have
+    // given can't be ruled out.
+    fn solutions_for(
+        &self,
+        ranges: &[(usize, u128, u128)],
+    ) -> Result<Vec<(u128, u128)>, ScheduleError> {
+        let mut offsets: Vec<(usize, u128)> = ranges.iter().map(|(i, low, _)| (*i, *low)).collect();
+        let mut solutions = Vec::<(u128, u128)>::new();
+        let mut first_err = None;
+        loop {
+            match self.solve_offsets(&offsets) {
+                Ok(solution) => solutions.push(solution),
+                Err(err @ ScheduleError::Overflow { .. }) => return Err(err),
+                Err(err) => {
+                    first_err.get_or_insert(err);
+                }
+            }
+            // Move on to the next combination of offsets, like an odometer
+            let mut digit = 0;
+            loop {
+                if digit == ranges.len() {
+                    return match first_err {
+                        Some(err) if solutions.is_empty() => Err(err),
+                        _ => Ok(solutions),
+                    };
+                }
+                let (_, low, high) = ranges[digit];
+                if offsets[digit].1 < high {
+                    offsets[digit].1 += 1;
+                    break;
+                }
+                offsets[digit].1 = low;
+                digit += 1;
+            }
+        }
+    }
+
+    fn solutions(&self) -> Result<Vec<(u128, u128)>, ScheduleError> {
+        self.solutions_for(&self.offset_ranges()?)
+    }
+
+    // The earliest time where each bus leaves `arrival` minutes after it.
+    // Frequencies don't need to be coprime, but if they share a factor there
+    // may be no time that works for every bus.
+    fn calculate_time(&self) -> Result<u128, ScheduleError> {
+        Ok(self
+            .solutions()?
+            .iter()
+            .map(|(time, _)| *time)
+            .min()
+            .unwrap())
+    }
+}
+
+fn main() {
+    // Usage: day13 [--timetable N] [--simulate | --simulate-from TIME] [--between START END]
+    //              [--input FILE] [--time | --bench] [--record]
+    let mut timetable = None;
+    let mut between = None;
+    let mut input = None;
+    let mut simulate = None;
+    let mut mode = Mode::Off;
+    let mut record = false;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "--timetable" => {
+                timetable = args
+                    .next()
+                    .map(|n| n.parse::<usize>().expect("--timetable needs a number"))
+            }
+            "--simulate" => simulate = Some(SimulationStart::Answer),
+            "--simulate-from" => {
+                simulate = args
+                    .next()
+                    .map(|n| n.parse::<u128>().expect("--simulate-from needs a time"))
+                    .map(SimulationStart::Time)
+            }
+            "--between" => {
+                let mut time = || {
+                    args.next()
+                        .and_then(|n| n.parse::<u128>().ok())
+                        .expect("--between needs a start and end time")
+                };
+                between = Some((time(), time()));
+            }
+            "--input" => input = Some(args.next().expect("--input needs a file")),
+            "--time" | "--bench" => mode = Mode::from_arg(&arg).unwrap(),
+            "--record" => record = true,
+            _ => panic!("Unknown argument {}", arg),
+        }
+    }
+    let text = match input {
+        Some(path) => std::fs::read_to_string(&path).expect("Could not read the input file"),
+        None => String::from(include_str!("../input_data.txt")),
+    };
+    let lines: &[&str] = &text.lines().collect::<Vec<&str>>();
+    let mut timings = Timings::new(mode);
+    let mut answers = AnswerCheck::new(
+        env!("CARGO_MANIFEST_DIR"),
+        env!("CARGO_PKG_NAME"),
+        &text,
+        record,
+    );
+    let bus_schedule = match timings.time("parse", || BusSchedule::try_from(lines)) {
+        Ok(bus_schedule) => bus_schedule,
+        Err(err) => {
+            println!("Could not parse the bus schedule: {}", err);
+            return;
+        }
+    };
+    println!("We parsed this bus schedule: {:?}", bus_schedule);
+    if let Ok(departure) = bus_schedule.earliest_departure() {
+        println!(
+            "The first bus is {}, leaving at {} after a {} minute wait",
+            departure.bus, departure.time, departure.wait
+        );
+    }
+    match timings.time("part a", || bus_schedule.calculate_day_a()) {
+        Ok(day_a_ret) => {
+            println!("Day a result: {}", day_a_ret);
+            answers.check("a", day_a_ret);
+        }
+        Err(err) => println!("Day a has no answer: {}", err),
+    }
+    if let Some(count) = timetable {
+        match bus_schedule.timetable(count) {
+            Ok(timetable) => print!("{}", timetable),
+            Err(err) => println!("Could not write the timetable: {}", err),
+        }
+    }
+    let day_b = timings.time("part b", || bus_schedule.calculate_time());
+    match day_b.as_ref() {
+        Ok(&day_b_ret) => {
+            println!("Day b, earliest time is: {}", day_b_ret);
+            answers.check("b", day_b_ret);
+            let unsatisfied = simulation::unsatisfied_buses(&bus_schedule, day_b_ret);
+            if !unsatisfied.is_empty() {
+                println!("But these buses don't leave on time: {:?}", unsatisfied);
+            }
+        }
+        Err(err) => {
+            println!("Day b has no answer: {}", err);
+            // Only worth dropping buses when they really can't all line up,
+            // not when the numbers are just too big
+            if let ScheduleError::NoSolution { .. } | ScheduleError::ConflictingConstraints(_) = err
+            {
+                match alignment::best_partial_alignment(&bus_schedule) {
+                    Ok(partial) => println!(
+                        "The most buses that line up is {}, first at {}: {:?}",
+                        partial.buses.len(),
+                        partial.time,
+                        partial.buses
+                    ),
+                    Err(err @ ScheduleError::SearchLimit(_)) => {
+                        println!("Gave up looking for the most buses that line up: {}", err)
+                    }
+                    Err(err) => println!("No buses line up at all: {}", err),
+                }
+            }
+        }
+    }
+    if let Some(start) = simulate.and_then(|start| start.time(day_b.as_ref().ok().copied())) {
+        let (minutes, truncated) = simulation::timeline_minutes(bus_schedule.span());
+        print!("{}", simulation::timeline(&bus_schedule, start, minutes));
+        if truncated {
+            println!("Timeline truncated after {} minutes", minutes);
+        }
+    }
+    if let Some((start, end)) = between {
+        match alignment::valid_times(&bus_schedule, start, Some(end)) {
+            Ok(times) => {
+                println!("Every time from {} to {} that works:", start, end);
+                for time in times {
+                    println!("  {}", time);
+                }
+            }
+            Err(err) => println!("No times work: {}", err),
+        }
+    }
+    timings.finish(env!("CARGO_MANIFEST_DIR"));
+    answers.finish();
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{BusSchedule, Departure, ScheduleError};
+    use proptest::prelude::*;
+    use std::convert::TryFrom;
+
+    // The original incremental sieve, kept to check the CRT solver against.
+    // Only works if the frequencies are pairwise coprime, otherwise it never finishes.
+    fn sieve_calculate_time(schedule: &BusSchedule) -> u64 {
         // When doing a brute force search, we can go up by modulo all the values we've currently
         // found for.
         let mut increment: u64 = 1;
         let mut calculated_time: u64 = 0;
-        for bus in self.buses.iter() {
+        for bus in schedule.buses.iter() {
             loop {
+                let arrival = bus.arrival.unwrap();
                 if (calculated_time % (bus.frequency as u64))
-                    == ((bus.frequency - (bus.arrival % bus.frequency)) % bus.frequency) as u64
+                    == ((bus.frequency - (arrival % bus.frequency)) % bus.frequency) as u64
                 {
                     increment *= bus.frequency as u64;
                     break;
@@ -47,25 +605,11 @@ impl BusSchedule {
         }
         calculated_time
     }
-}
-
-fn main() {
-    let lines = &include_str!("../input_data.txt")
-        .lines()
-        .collect::<Vec<&str>>()[0..2];
-    let bus_schedule = BusSchedule::from(lines);
-    let day_b_ret = bus_schedule.calculate_time();
-    println!("We parsed this bus schedule: {:?}", bus_schedule);
-    println!("Day b, earliest time is: {}", day_b_ret);
-}
 
-#[cfg(test)]
-mod test {
-    use crate::BusSchedule;
     #[test]
     fn test_parsing() {
         let lines: &[&str] = &["939", "7,13,x,x,59,x,31,19"];
-        let bus_schedule: BusSchedule = BusSchedule::from(lines);
+        let bus_schedule: BusSchedule = BusSchedule::try_from(lines).unwrap();
         assert_eq!(bus_schedule.start_time, 939);
         assert_eq!(bus_schedule.buses.len(), 5);
         let buses = &bus_schedule.buses[..];
@@ -74,7 +618,7 @@ mod test {
             .enumerate()
         {
             assert_eq!(buses[i].frequency, *frequency);
-            assert_eq!(buses[i].arrival, *arrival);
+            assert_eq!(buses[i].arrival, Some(*arrival));
         }
     }
 
@@ -90,9 +634,318 @@ mod test {
         .iter()
         {
             assert_eq!(
-                BusSchedule::from(&["0", schedule_string][..]).calculate_time(),
-                *expected_time
+                BusSchedule::try_from(&["0", schedule_string][..])
+                    .unwrap()
+                    .calculate_time(),
+                Ok(*expected_time)
+            );
+        }
+    }
+
+    #[test]
+    fn test_matches_sieve() {
+        for schedule_string in [
+            "7,13,x,x,59,x,31,19",
+            "17,x,13,19",
+            "3,5,7,11,13,17,19,23",
+            "101,x,x,103,x,107",
+            include_str!("../input_data.txt").lines().nth(1).unwrap(),
+        ]
+        .iter()
+        {
+            let bus_schedule = BusSchedule::try_from(&["0", schedule_string][..]).unwrap();
+            assert_eq!(
+                bus_schedule.calculate_time(),
+                Ok(sieve_calculate_time(&bus_schedule) as u128),
+                "{}",
+                schedule_string
             );
         }
     }
+
+    #[test]
+    fn test_shared_factors() {
+        // t = 0 mod 4, t + 2 = 0 mod 6
+        assert_eq!(
+            BusSchedule::try_from(&["0", "4,x,6"][..])
+                .unwrap()
+                .calculate_time(),
+            Ok(4)
+        );
+        // t = 0 mod 4 is even, t + 1 = 0 mod 6 is odd
+        assert_eq!(
+            BusSchedule::try_from(&["0", "4,6"][..])
+                .unwrap()
+                .calculate_time(),
+            Err(ScheduleError::NoSolution { bus: 6, arrival: 1 })
+        );
+    }
+
+    #[test]
+    fn test_earliest_departure() {
+        let lines: &[&str] = &["939", "7,13,x,x,59,x,31,19"];
+        let bus_schedule = BusSchedule::try_from(lines).unwrap();
+        assert_eq!(
+            bus_schedule.earliest_departure(),
+            Ok(Departure {
+                bus: 59,
+                time: 944,
+                wait: 5
+            })
+        );
+        assert_eq!(bus_schedule.calculate_day_a(), Ok(295));
+        let order: Vec<(u128, u128)> = bus_schedule
+            .upcoming_departures()
+            .unwrap()
+            .iter()
+            .map(|departure| (departure.bus, departure.wait))
+            .collect();
+        assert_eq!(order, [(59, 5), (7, 6), (13, 10), (19, 11), (31, 22)]);
+    }
+
+    #[test]
+    fn test_departure_at_start_time() {
+        let bus_schedule = BusSchedule::try_from(&["20", "7,x,5"][..]).unwrap();
+        assert_eq!(bus_schedule.calculate_day_a(), Ok(0));
+        assert_eq!(bus_schedule.earliest_departure().unwrap().time, 20);
+    }
+
+    #[test]
+    fn test_timetable() {
+        let lines: &[&str] = &["939", "7,13,x,x,59"];
+        assert_eq!(
+            BusSchedule::try_from(lines).unwrap().timetable(3).unwrap(),
+            "bus 7: 945 952 959\n\
+             bus 13: 949 962 975\n\
+             bus 59: 944 1003 1062\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for (lines, expected) in [
+            (&["939"][..], ScheduleError::MissingLine),
+            (
+                &["soon", "7,13"][..],
+                ScheduleError::InvalidStartTime(String::from("soon")),
+            ),
+            (
+                &["939", "7,x,0,13"][..],
+                ScheduleError::ZeroFrequency { position: 2 },
+            ),
+            (
+                &["939", "7,y"][..],
+                ScheduleError::InvalidFrequency {
+                    position: 1,
+                    value: String::from("y"),
+                },
+            ),
+            (
+                &["939", "7,999999999999999999999999999999999999999999"][..],
+                ScheduleError::InvalidFrequency {
+                    position: 1,
+                    value: String::from("999999999999999999999999999999999999999999"),
+                },
+            ),
+        ]
+        .iter()
+        {
+            assert_eq!(BusSchedule::try_from(*lines).unwrap_err(), *expected);
+        }
+    }
+
+    #[test]
+    fn test_no_buses() {
+        let bus_schedule = BusSchedule::try_from(&["939", "x,x"][..]).unwrap();
+        assert_eq!(bus_schedule.calculate_day_a(), Err(ScheduleError::NoBuses));
+        assert_eq!(bus_schedule.calculate_time(), Ok(0));
+    }
+
+    #[test]
+    fn test_answer_bigger_than_u64() {
+        let bus_schedule =
+            BusSchedule::try_from(&["0", "1000000007,x,998244353,x,1000000009,x,x,1000000021"][..])
+                .unwrap();
+        let time = bus_schedule.calculate_time().unwrap();
+        assert!(time > u64::MAX as u128);
+        assert_eq!(time, 925691601625588362350936611314943659);
+        for bus in bus_schedule.buses.iter() {
+            assert_eq!((time + bus.arrival.unwrap()) % bus.frequency, 0);
+        }
+    }
+
+    #[test]
+    fn test_overflow() {
+        let bus_schedule = BusSchedule::try_from(
+            &["0", "1000000007,998244353,1000000009,1000000021,1000000033"][..],
+        )
+        .unwrap();
+        assert_eq!(
+            bus_schedule.calculate_time(),
+            Err(ScheduleError::Overflow { bus: 1000000033 })
+        );
+        let bus_schedule =
+            BusSchedule::try_from(&["340282366920938463463374607431768211455", "7"][..]).unwrap();
+        assert_eq!(
+            bus_schedule.calculate_day_a(),
+            Err(ScheduleError::Overflow { bus: 7 })
+        );
+    }
+
+    fn explicit(text: &str) -> BusSchedule {
+        BusSchedule::try_from(&text.lines().collect::<Vec<&str>>()[..]).unwrap()
+    }
+
+    #[test]
+    fn test_explicit_matches_puzzle_format() {
+        let schedule = explicit(
+            "bus 7 offset 0\n\
+             bus 13 offset 1\n\
+             bus 59 offset 4\n\
+             bus 31 offset 6\n\
+             bus 19 offset 7",
+        );
+        assert_eq!(schedule.calculate_time(), Ok(1068781));
+    }
+
+    #[test]
+    fn test_phases() {
+        let schedule = explicit(include_str!("../test_data_explicit.txt"));
+        assert_eq!(
+            schedule.earliest_departure(),
+            Ok(Departure {
+                bus: 5,
+                time: 940,
+                wait: 1
+            })
+        );
+        assert_eq!(
+            schedule.timetable(2).unwrap().lines().nth(2),
+            Some("bus 7: 941 948")
+        );
+        // Bus 4 every 4 minutes but only from minute 100, bus 3 a minute later
+        let schedule = explicit("bus 4 phase 100 offset 0\nbus 3 offset 1");
+        assert_eq!(schedule.calculate_time(), Ok(104));
+    }
+
+    #[test]
+    fn test_constraints() {
+        let schedule = explicit(include_str!("../test_data_explicit.txt"));
+        assert_eq!(schedule.calculate_time(), Ok(24));
+        assert_eq!(schedule.span(), 2);
+        // Bus 2 only leaves at odd times, but has to leave when bus 4 does
+        let schedule = explicit(
+            "bus 4 offset 0\n\
+             bus 2 phase 1\n\
+             constraint 2 within 0 after 4",
+        );
+        assert_eq!(
+            schedule.calculate_time(),
+            Err(ScheduleError::NoSolution { bus: 2, arrival: 0 })
+        );
+        let schedule = explicit(
+            "bus 4 offset 0\n\
+             bus 5 offset 10\n\
+             bus 3\n\
+             constraint 3 within 1 after 4\n\
+             constraint 3 within 1 after 5",
+        );
+        assert_eq!(
+            schedule.calculate_time(),
+            Err(ScheduleError::ConflictingConstraints(3))
+        );
+    }
+
+    #[test]
+    fn test_huge_constraint_window() {
+        // Bus 7 can leave any time in the next 10^12 minutes, so it doesn't
+        // hold bus 4 back at all
+        let schedule = explicit(
+            "bus 4 offset 0\n\
+             bus 7\n\
+             constraint 7 within 1000000000000 after 4",
+        );
+        assert_eq!(
+            schedule.offset_ranges(),
+            Ok(vec![(0, 0, 0), (1, 999999999994, 1000000000000)])
+        );
+        assert_eq!(schedule.calculate_time(), Ok(0));
+        let schedule = explicit(
+            "bus 4 offset 0\n\
+             bus 5 offset 1\n\
+             bus 7 phase 100\n\
+             bus 9\n\
+             constraint 7 within 1000000000000 after 4\n\
+             constraint 9 within 340282366920938463463374607431768211454 after 5",
+        );
+        assert_eq!(schedule.calculate_time(), Ok(4));
+        // The window is cut down, but bus 7 still can't leave before minute 100
+        let schedule = explicit(
+            "bus 4 offset 0\n\
+             bus 7 phase 100\n\
+             constraint 7 within 50 after 4",
+        );
+        assert_eq!(schedule.calculate_time(), Ok(52));
+        assert!(crate::simulation::unsatisfied_buses(&schedule, 52).is_empty());
+        assert_eq!(crate::simulation::unsatisfied_buses(&schedule, 48), [7]);
+    }
+
+    #[test]
+    fn test_generated() {
+        for (seed, size) in [(1, 1), (2, 5), (3, 9), (4, 30)] {
+            let generated = generators::day13::generate(seed, size);
+            let lines: Vec<&str> = generated.input.lines().collect();
+            let schedule = BusSchedule::try_from(&lines[..]).unwrap();
+            assert_eq!(schedule.calculate_day_a(), Ok(generated.part_a));
+            assert_eq!(schedule.calculate_time(), Ok(generated.part_b));
+        }
+    }
+
+    // Small enough primes that the sieve's times fit in a u64
+    const PRIMES: [u128; 17] = [
+        2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59,
+    ];
+
+    // A bus list in the puzzle's format, each bus preceded by a few `x`s
+    fn bus_list_strategy() -> impl Strategy<Value = String> {
+        prop::sample::subsequence(PRIMES.to_vec(), 1..8)
+            .prop_shuffle()
+            .prop_flat_map(|ids| {
+                let gaps = prop::collection::vec(0..4usize, ids.len());
+                (Just(ids), gaps)
+            })
+            .prop_map(|(ids, gaps)| {
+                let mut list: Vec<String> = vec![];
+                for (id, gap) in ids.iter().zip(gaps) {
+                    list.extend(std::iter::repeat_n(String::from("x"), gap));
+                    list.push(id.to_string());
+                }
+                list.join(",")
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn test_matches_sieve_generated(buses in bus_list_strategy()) {
+            let schedule = BusSchedule::try_from(&["0", &buses[..]][..]).unwrap();
+            prop_assert_eq!(
+                schedule.calculate_time(),
+                Ok(sieve_calculate_time(&schedule) as u128)
+            );
+        }
+
+        // Against waiting at the stop a minute at a time until a bus leaves
+        #[test]
+        fn test_earliest_departure_matches_waiting(
+            buses in bus_list_strategy(),
+            start_time in 0..100_000u128,
+        ) {
+            let schedule =
+                BusSchedule::try_from(&[&start_time.to_string()[..], &buses[..]][..]).unwrap();
+            let waited = (0..)
+                .find(|wait| schedule.buses.iter().any(|bus| bus.departs_at(start_time + wait)))
+                .unwrap();
+            prop_assert_eq!(schedule.earliest_departure().unwrap().wait, waited);
+        }
+    }
 }
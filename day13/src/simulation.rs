@@ -0,0 +1,251 @@
+// Step through time minute by minute and see which buses leave, like the
+// table in the puzzle's explanation. Useful for checking an answer by eye,
+// and for checking it for real with `unsatisfied_buses`.
+use crate::BusSchedule;
+use std::convert::TryFrom;
+
+/// The most minutes `--simulate` will print, however far apart the offsets are
+pub const MAX_TIMELINE_MINUTES: usize = 500;
+
+/// The buses leaving at one minute, in the same order as the schedule's buses
+#[derive(Debug, PartialEq)]
+pub struct Tick {
+    pub time: u128,
+    pub departures: Vec<bool>,
+}
+
+/// Every minute from a start time onwards (until the time no longer fits in a u128)
+pub struct Simulation<'a> {
+    schedule: &'a BusSchedule,
+    time: Option<u128>,
+}
+
+impl<'a> Simulation<'a> {
+    pub fn new(schedule: &'a BusSchedule, start_time: u128) -> Simulation<'a> {
+        Simulation {
+            schedule,
+            time: Some(start_time),
+        }
+    }
+}
+
+impl Iterator for Simulation<'_> {
+    type Item = Tick;
+
+    fn next(&mut self) -> Option<Tick> {
+        let time = self.time?;
+        self.time = time.checked_add(1);
+        Some(Tick {
+            time,
+            departures: self
+                .schedule
+                .buses
+                .iter()
+                .map(|bus| bus.departs_at(time))
+                .collect(),
+        })
+    }
+}
+
+/// Where `--simulate` starts its timeline: at day b's answer, or at any time given
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SimulationStart {
+    Answer,
+    Time(u128),
+}
+
+impl SimulationStart {
+    /// The time to start at, or `None` when starting at an answer that doesn't exist
+    pub fn time(self, answer: Option<u128>) -> Option<u128> {
+        match self {
+            SimulationStart::Answer => answer,
+            SimulationStart::Time(time) => Some(time),
+        }
+    }
+}
+
+/// How many minutes of timeline it takes to see every bus with an offset
+/// leave, given the schedule's `span`, cut down to `MAX_TIMELINE_MINUTES`.
+/// Also says whether it was cut down.
+pub fn timeline_minutes(span: u128) -> (usize, bool) {
+    match usize::try_from(span.saturating_add(1)) {
+        Ok(minutes) if minutes <= MAX_TIMELINE_MINUTES => (minutes, false),
+        _ => (MAX_TIMELINE_MINUTES, true),
+    }
+}
+
+/// A table with a row per minute and a column per bus, with `D` where a bus
+/// leaves and `.` where it doesn't, e.g.
+///
+/// ```text
+/// time     bus 7  bus 13  bus 59
+/// 1068781    D      .       .
+/// 1068782    .      D       .
+/// ```
+pub fn timeline(schedule: &BusSchedule, start_time: u128, minutes: usize) -> String {
+    let ticks: Vec<Tick> = Simulation::new(schedule, start_time)
+        .take(minutes)
+        .collect();
+    let time_width = ticks
+        .iter()
+        .map(|tick| tick.time.to_string().len())
+        .chain(std::iter::once("time".len()))
+        .max()
+        .unwrap();
+    let headers: Vec<String> = schedule
+        .buses
+        .iter()
+        .map(|bus| format!("bus {}", bus.id))
+        .collect();
+    let mut ret = format!("{:<width$}", "time", width = time_width);
+    for header in headers.iter() {
+        ret += &format!("  {}", header);
+    }
+    ret += "\n";
+    for tick in ticks.iter() {
+        let mut row = format!("{:<width$}", tick.time, width = time_width);
+        for (header, departs) in headers.iter().zip(tick.departures.iter()) {
+            let mark = if *departs { "D" } else { "." };
+            row += &format!("  {:^width$}", mark, width = header.len());
+        }
+        ret += row.trim_end();
+        ret += "\n";
+    }
+    ret
+}
+
+/// The id of every bus that does not leave at its offset after `time`, or
+/// that breaks one of its constraints. Empty when `time` is a valid answer for day b.
+pub fn unsatisfied_buses(schedule: &BusSchedule, time: u128) -> Vec<u128> {
+    let mut unsatisfied: Vec<u128> = schedule
+        .buses
+        .iter()
+        .filter(|bus| match bus.arrival {
+            Some(arrival) => time
+                .checked_add(arrival)
+                .is_none_or(|departure| !bus.departs_at(departure)),
+            None => false,
+        })
+        .map(|bus| bus.id)
+        .collect();
+    for constraint in schedule.constraints.iter() {
+        let (bus, after) = match (schedule.bus(constraint.bus), schedule.bus(constraint.after)) {
+            (Some(bus), Some(after)) => (bus, after),
+            _ => continue,
+        };
+        // The bus's first departure from when `after` leaves has to be soon enough
+        let in_time = after
+            .arrival
+            .and_then(|arrival| time.checked_add(arrival))
+            .and_then(|leaves| {
+                let latest = leaves.checked_add(constraint.within)?;
+                Some(bus.next_departure(leaves).ok()? <= latest)
+            });
+        if in_time != Some(true) && !unsatisfied.contains(&bus.id) {
+            unsatisfied.push(bus.id);
+        }
+    }
+    unsatisfied
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn example() -> BusSchedule {
+        BusSchedule::try_from(&["939", "7,13,x,x,59,x,31,19"][..]).unwrap()
+    }
+
+    #[test]
+    fn test_simulation() {
+        let schedule = example();
+        let ticks: Vec<Tick> = Simulation::new(&schedule, 1068781).take(2).collect();
+        assert_eq!(
+            ticks,
+            [
+                Tick {
+                    time: 1068781,
+                    departures: vec![true, false, false, false, false]
+                },
+                Tick {
+                    time: 1068782,
+                    departures: vec![false, true, false, false, false]
+                },
+            ]
+        );
+        assert_eq!(Simulation::new(&schedule, u128::MAX).count(), 1);
+    }
+
+    #[test]
+    fn test_timeline() {
+        let schedule = example();
+        let timeline = timeline(&schedule, 1068781, 8);
+        let lines: Vec<&str> = timeline.lines().collect();
+        assert_eq!(lines[0], "time     bus 7  bus 13  bus 59  bus 31  bus 19");
+        assert_eq!(lines[1], "1068781    D      .       .       .       .");
+        assert_eq!(lines[2], "1068782    .      D       .       .       .");
+        assert_eq!(lines[5], "1068785    .      .       D       .       .");
+        assert_eq!(lines[7], "1068787    .      .       .       D       .");
+        assert_eq!(lines[8], "1068788    D      .       .       .       D");
+        assert_eq!(lines.len(), 9);
+    }
+
+    #[test]
+    fn test_timeline_clamped() {
+        assert_eq!(timeline_minutes(4), (5, false));
+        assert_eq!(
+            timeline_minutes(MAX_TIMELINE_MINUTES as u128 - 1),
+            (MAX_TIMELINE_MINUTES, false)
+        );
+        assert_eq!(timeline_minutes(u128::MAX), (MAX_TIMELINE_MINUTES, true));
+        let schedule =
+            BusSchedule::try_from(&["bus 7 offset 0", "bus 13 offset 1000000000000"][..]).unwrap();
+        let (minutes, truncated) = timeline_minutes(schedule.span());
+        assert!(truncated);
+        let timeline = timeline(&schedule, 0, minutes);
+        assert_eq!(timeline.lines().count(), MAX_TIMELINE_MINUTES + 1);
+    }
+
+    #[test]
+    fn test_simulation_start() {
+        assert_eq!(SimulationStart::Answer.time(Some(5)), Some(5));
+        assert_eq!(SimulationStart::Answer.time(None), None);
+        assert_eq!(SimulationStart::Time(939).time(None), Some(939));
+        assert_eq!(SimulationStart::Time(939).time(Some(5)), Some(939));
+    }
+
+    #[test]
+    fn test_timeline_from_any_time() {
+        let schedule = example();
+        let timeline = timeline(&schedule, 939, 6);
+        let lines: Vec<&str> = timeline.lines().collect();
+        assert_eq!(lines[0], "time  bus 7  bus 13  bus 59  bus 31  bus 19");
+        assert_eq!(lines[1], "939     .      .       .       .       .");
+        assert_eq!(lines[6], "944     .      .       D       .       .");
+        assert_eq!(lines.len(), 7);
+    }
+
+    #[test]
+    fn test_unsatisfied_buses() {
+        let schedule = example();
+        assert!(unsatisfied_buses(&schedule, 1068781).is_empty());
+        assert_eq!(unsatisfied_buses(&schedule, 1068782), [7, 13, 59, 31, 19]);
+        assert_eq!(
+            unsatisfied_buses(&schedule, schedule.calculate_time().unwrap()),
+            Vec::<u128>::new()
+        );
+    }
+
+    #[test]
+    fn test_phases_and_constraints() {
+        let lines: Vec<&str> = include_str!("../test_data_explicit.txt").lines().collect();
+        let schedule = BusSchedule::try_from(&lines[..]).unwrap();
+        assert!(unsatisfied_buses(&schedule, 24).is_empty());
+        // Bus 4 leaves at 54 and bus 5 at 55, but bus 7 not until 59
+        assert_eq!(unsatisfied_buses(&schedule, 54), [7]);
+        let timeline = timeline(&schedule, 0, 4);
+        let lines: Vec<&str> = timeline.lines().collect();
+        assert_eq!(lines[1], "0       D      D      .");
+        assert_eq!(lines[4], "3       .      .      D");
+    }
+}
@@ -1,4 +1,5 @@
 use anyhow::Result;
+use harness::{AnswerCheck, Args, Timings};
 use std::convert::TryFrom;
 
 struct PasswordLine {
@@ -20,6 +21,17 @@ impl PasswordLineErrors {
     }
 }
 
+impl std::fmt::Display for PasswordLineErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PasswordLineErrors::ParseInt(err) => write!(f, "Invalid position: {}", err),
+            PasswordLineErrors::Format => write!(f, "Expected \"a-b c: password\""),
+        }
+    }
+}
+
+impl std::error::Error for PasswordLineErrors {}
+
 impl PasswordLine {
     fn valid_day_a(&self) -> bool {
         let letter_count = self.password.matches(self.c).count();
@@ -66,16 +78,27 @@ impl TryFrom<String> for PasswordLine {
 }
 
 fn main() -> Result<()> {
-    let s: Vec<PasswordLine> = include_str!("../input_data.txt")
-        .lines()
-        .map(String::from)
-        .map(PasswordLine::try_from)
-        .map(Result::unwrap)
-        .collect();
-    let day_a = count_valid_passwords_day_a(s.iter());
+    // Usage: day2 [--time | --bench] [--record]
+    let args = Args::parse(std::env::args().skip(1));
+    let mut timings = Timings::new(args.mode);
+    let input = include_str!("../input_data.txt");
+    let mut answers = AnswerCheck::new(
+        env!("CARGO_MANIFEST_DIR"),
+        env!("CARGO_PKG_NAME"),
+        input,
+        args.record,
+    );
+    let s = timings.time("parse", || {
+        parsing::parse_lines(input, |line| PasswordLine::try_from(String::from(line)))
+    })?;
+    let day_a = timings.time("part a", || count_valid_passwords_day_a(s.iter()));
     println!("Day a valid passwords: {}", day_a);
-    let day_b = count_valid_passwords_day_b(s.iter());
+    answers.check("a", day_a);
+    let day_b = timings.time("part b", || count_valid_passwords_day_b(s.iter()));
     println!("Day b valid passwords: {}", day_b);
+    answers.check("b", day_b);
+    timings.finish(env!("CARGO_MANIFEST_DIR"));
+    answers.finish();
 
     Ok(())
 }
@@ -91,3 +114,72 @@ fn count_valid_passwords_day_b(password_lines: std::slice::Iter<PasswordLine>) -
         .filter(|password_line| password_line.valid_day_b())
         .count()
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{count_valid_passwords_day_a, count_valid_passwords_day_b, PasswordLine};
+    use proptest::prelude::*;
+    use std::convert::TryFrom;
+
+    // The policies checked a letter at a time, to test the real versions against.
+    // Part b treats a position past the end of the password as invalid.
+    fn naive_valid(a: usize, b: usize, c: char, password: &str) -> (bool, bool) {
+        let mut count = 0;
+        let mut at_a = None;
+        let mut at_b = None;
+        for (i, letter) in password.chars().enumerate() {
+            if letter == c {
+                count += 1;
+            }
+            if i + 1 == a {
+                at_a = Some(letter == c);
+            }
+            if i + 1 == b {
+                at_b = Some(letter == c);
+            }
+        }
+        let day_b = match (at_a, at_b) {
+            (Some(at_a), Some(at_b)) => at_a != at_b,
+            _ => false,
+        };
+        (a <= count && count <= b, day_b)
+    }
+
+    #[test]
+    fn test_generated() {
+        for seed in 0..10 {
+            let generated = generators::day2::generate(seed, 1000);
+            let lines = parsing::parse_lines(&generated.input, |line| {
+                PasswordLine::try_from(String::from(line))
+            })
+            .unwrap();
+            assert_eq!(count_valid_passwords_day_a(lines.iter()), generated.part_a);
+            assert_eq!(count_valid_passwords_day_b(lines.iter()), generated.part_b);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_matches_naive(
+            lines in prop::collection::vec((1..12usize, 1..12usize, "[a-c]", "[a-c]{1,10}"), 1..20)
+        ) {
+            let input: String = lines
+                .iter()
+                .map(|(a, b, c, password)| format!("{}-{} {}: {}\n", a, b, c, password))
+                .collect();
+            let parsed = parsing::parse_lines(&input, |line| {
+                PasswordLine::try_from(String::from(line))
+            })
+            .unwrap();
+            let mut day_a = 0;
+            let mut day_b = 0;
+            for (a, b, c, password) in lines.iter() {
+                let (valid_a, valid_b) = naive_valid(*a, *b, c.chars().next().unwrap(), password);
+                day_a += valid_a as usize;
+                day_b += valid_b as usize;
+            }
+            prop_assert_eq!(count_valid_passwords_day_a(parsed.iter()), day_a);
+            prop_assert_eq!(count_valid_passwords_day_b(parsed.iter()), day_b);
+        }
+    }
+}
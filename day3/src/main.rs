@@ -1,76 +1,51 @@
-#[derive(std::hash::Hash, PartialEq, Eq, Debug)]
-struct Point {
-    x: isize,
-    y: isize,
-}
+use grid::{Edge, Grid, GridParseError, Point};
+use harness::{AnswerCheck, Args, Timings};
+use std::convert::TryFrom;
 
 #[derive(Debug)]
 struct SkiMap {
-    width: usize,
-    height: usize,
-    positions: Vec<Vec<bool>>,
+    // The slope repeats endlessly to the right, but stops at the bottom
+    trees: Grid<bool>,
 }
 
 impl std::fmt::Display for SkiMap {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut ret = String::from("");
-        for y in 0..self.height {
-            for x in 0..self.width {
-                ret += match self.get(&Point {
-                    x: x as isize,
-                    y: y as isize,
-                }) {
-                    Some(true) => "#",
-                    _ => ".",
-                };
-            }
-            ret += "\n";
-        }
-        write!(f, "{}", ret)
+        write!(
+            f,
+            "{}",
+            self.trees.render(|tree| if *tree { '#' } else { '.' })
+        )
     }
 }
 
-impl From<&[&[u8]]> for SkiMap {
-    fn from(lines: &[&[u8]]) -> SkiMap {
-        let width = lines[0].len();
-        let height = lines.len();
-        let mut positions = vec![vec![false; width]; height];
-        for y in 0..height {
-            if lines[y].len() != width {
-                panic!("Invalid file. Not all lines are the same width");
-            }
-            for x in 0..width {
-                positions[y][x] = lines[y][x] == b'#';
-            }
-        }
-        println!("Creating skimap with width {}, height {}", width, height);
-        SkiMap {
-            width,
-            height,
-            positions,
-        }
+// A square on the map that is neither '#' (a tree) nor '.' (open)
+#[derive(Debug, PartialEq)]
+struct UnknownSquare(char);
+
+impl std::fmt::Display for UnknownSquare {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "'{}' is not a tree or an open square", self.0)
     }
 }
 
-impl From<&str> for SkiMap {
-    fn from(input: &str) -> SkiMap {
-        let lines = &input.lines().map(str::as_bytes).collect::<Vec<&[u8]>>()[..];
-        SkiMap::from(lines)
+impl TryFrom<&str> for SkiMap {
+    type Error = GridParseError<UnknownSquare>;
+
+    fn try_from(input: &str) -> Result<SkiMap, GridParseError<UnknownSquare>> {
+        let trees = Grid::parse(input, |c| match c {
+            '#' => Ok(true),
+            '.' => Ok(false),
+            _ => Err(UnknownSquare(c)),
+        })?;
+        Ok(SkiMap {
+            trees: trees.with_edges(Edge::Wrap, Edge::None),
+        })
     }
 }
 
 impl SkiMap {
     fn get(&self, position: &Point) -> Option<bool> {
-        // Get the value, wrapping X positions endlessly within the range
-        let width = self.width as isize;
-        let mapped_point = Point {
-            x: (((position.x % width) + width) % width),
-            ..*position
-        };
-        self.positions
-            .get(mapped_point.y as usize)
-            .and_then(|line| line.get(mapped_point.x as usize))
-            .copied()
+        self.trees.get(*position).copied()
     }
 
     fn count_vector(&self, movement: &Point) -> usize {
@@ -87,10 +62,7 @@ impl SkiMap {
                 Some(false) => (),
                 None => break,
             };
-            position = Point {
-                x: position.x + movement.x,
-                y: position.y + movement.y,
-            };
+            position += *movement;
         }
         count
     }
@@ -98,7 +70,7 @@ impl SkiMap {
     fn day_b_calculate(&self) -> usize {
         // Calculate the Day B result.
         // The product of how many trees are hit by doing the below movements.
-        vec![
+        [
             Point { x: 1, y: 1 },
             Point { x: 3, y: 1 },
             Point { x: 5, y: 1 },
@@ -112,17 +84,41 @@ impl SkiMap {
 }
 
 fn main() {
+    // Usage: day3 [--time | --bench] [--record]
+    let args = Args::parse(std::env::args().skip(1));
+    let mut timings = Timings::new(args.mode);
     let lines: &str = include_str!("../input_data.txt");
-    let ski_map = SkiMap::from(lines);
+    let mut answers = AnswerCheck::new(
+        env!("CARGO_MANIFEST_DIR"),
+        env!("CARGO_PKG_NAME"),
+        lines,
+        args.record,
+    );
+    let ski_map = match timings.time("parse", || SkiMap::try_from(lines)) {
+        Ok(ski_map) => ski_map,
+        Err(err) => {
+            println!("Could not parse the ski map: {}", err);
+            return;
+        }
+    };
+    println!(
+        "Created skimap with width {}, height {}",
+        ski_map.trees.width(),
+        ski_map.trees.height()
+    );
     println!("Parsed ski_map: {}", ski_map);
     println!("Calculating day_a:");
-    let day_a_tree_count = ski_map.count_vector(&Point { x: 3, y: 1 });
+    let day_a_tree_count = timings.time("part a", || ski_map.count_vector(&Point { x: 3, y: 1 }));
     println!(
         "Day a: hit {} trees on path down the mountain.",
         day_a_tree_count
     );
-    let day_b_tree_multiplier = ski_map.day_b_calculate();
+    answers.check("a", day_a_tree_count);
+    let day_b_tree_multiplier = timings.time("part b", || ski_map.day_b_calculate());
     println!("Day b: Our multiplier result is {}", day_b_tree_multiplier);
+    answers.check("b", day_b_tree_multiplier);
+    timings.finish(env!("CARGO_MANIFEST_DIR"));
+    answers.finish();
 }
 
 #[cfg(test)]
@@ -130,11 +126,29 @@ mod test {
 
     use crate::Point;
     use crate::SkiMap;
+    use crate::UnknownSquare;
+    use grid::GridParseError;
+    use proptest::prelude::*;
+    use std::convert::TryFrom;
+
+    // Trees hit indexing the rows directly, wrapping with a modulo, to test
+    // the grid version against
+    fn naive_count(rows: &[Vec<bool>], right: usize, down: usize) -> usize {
+        rows.iter()
+            .step_by(down)
+            .enumerate()
+            .filter(|(step, row)| row[(step * right) % row.len()])
+            .count()
+    }
+
+    fn map_strategy() -> impl Strategy<Value = Vec<Vec<bool>>> {
+        (1..12usize).prop_flat_map(|width| {
+            prop::collection::vec(prop::collection::vec(any::<bool>(), width), 1..30)
+        })
+    }
     #[test]
     fn test_get_function() {
-        let lines: &[&[u8]] = &[&[b'.', b'#'][..], &[b'#', b'.'][..]][..];
-        let map = SkiMap::from(lines);
-        println!("creating skimap {:?}", map);
+        let map = SkiMap::try_from(".#\n#.").unwrap();
         assert_eq!(map.get(&Point { x: 0, y: 0 }), Some(false));
         assert_eq!(map.get(&Point { x: 0, y: -1 }), None);
         assert_eq!(map.get(&Point { x: 0, y: 3 }), None);
@@ -146,17 +160,69 @@ mod test {
         assert_eq!(map.get(&Point { x: 8, y: 1 }), Some(true));
     }
 
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            SkiMap::try_from("..#\n.#").unwrap_err(),
+            GridParseError::RaggedLine {
+                line: 2,
+                width: 2,
+                expected: 3
+            }
+        );
+        assert_eq!(
+            SkiMap::try_from("..#\n.O.").unwrap_err(),
+            GridParseError::Cell {
+                line: 2,
+                column: 2,
+                error: UnknownSquare('O')
+            }
+        );
+        assert_eq!(SkiMap::try_from("").unwrap_err(), GridParseError::Empty);
+    }
+
     #[test]
     fn test_path() {
         let lines: &str = include_str!("../test_data.txt");
-        let map = SkiMap::from(lines);
+        let map = SkiMap::try_from(lines).unwrap();
         assert_eq!(map.count_vector(&Point { x: 3, y: 1 }), 7);
     }
 
     #[test]
     fn test_day_b_calculation() {
         let lines: &str = include_str!("../test_data.txt");
-        let map = SkiMap::from(lines);
+        let map = SkiMap::try_from(lines).unwrap();
         assert_eq!(map.day_b_calculate(), 336);
     }
+
+    #[test]
+    fn test_generated() {
+        for seed in 0..10 {
+            let generated = generators::day3::generate(seed, 1000);
+            let map = SkiMap::try_from(&generated.input[..]).unwrap();
+            assert_eq!(map.count_vector(&Point { x: 3, y: 1 }), generated.part_a);
+            assert_eq!(map.day_b_calculate(), generated.part_b);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_matches_naive(rows in map_strategy(), right in 0..40usize, down in 1..4usize) {
+            let text: String = rows
+                .iter()
+                .map(|row| {
+                    let line: String = row.iter().map(|tree| if *tree { '#' } else { '.' }).collect();
+                    line + "\n"
+                })
+                .collect();
+            let map = SkiMap::try_from(&text[..]).unwrap();
+            let movement = Point { x: right as isize, y: down as isize };
+            prop_assert_eq!(map.count_vector(&movement), naive_count(&rows, right, down));
+            let product: usize = [(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)]
+                .iter()
+                .map(|(right, down)| naive_count(&rows, *right, *down))
+                .product();
+            prop_assert_eq!(map.day_b_calculate(), product);
+        }
+    }
 }
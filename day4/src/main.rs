@@ -1,3 +1,7 @@
+use harness::{AnswerCheck, Args, Timings};
+use parsing::ParseError;
+use std::convert::Infallible;
+
 #[derive(Debug, PartialEq)]
 enum PassportField {
     IssueYear(String),
@@ -51,7 +55,7 @@ impl PassportField {
     }
 
     fn _is_lowercase_hex(c: &char) -> bool {
-        ('0'..='9').contains(c) || ('a'..='f').contains(c)
+        c.is_ascii_digit() || ('a'..='f').contains(c)
     }
 
     fn _validate_hcl(val: &str) -> bool {
@@ -106,22 +110,15 @@ impl PassportField {
 }
 
 impl Passport {
-    fn parse(input: &str) -> Passport {
+    // Keys we don't know about are skipped, and a key given twice is kept twice
+    fn parse(input: &str) -> Result<Passport, ParseError<Infallible>> {
         let mut keys = Vec::<PassportField>::new();
-        for line in input.lines() {
-            for key_val in line.split(' ') {
-                let mut key_val_split = key_val.split(':');
-                let key = key_val_split.next().unwrap();
-                let val = key_val_split
-                    .next()
-                    .expect("File must contain key:val format");
-
-                if let Ok(valid_field) = PassportField::new(key, val) {
-                    keys.push(valid_field);
-                }
+        for (key, val) in parsing::key_value_pairs(input)? {
+            if let Ok(valid_field) = PassportField::new(key, val) {
+                keys.push(valid_field);
             }
         }
-        Passport { keys }
+        Ok(Passport { keys })
     }
 
     fn is_valid_day_a(&self) -> bool {
@@ -147,21 +144,42 @@ fn count_valid_day_b(passports: &[Passport]) -> usize {
         .count()
 }
 
-fn parse_data(input_data: &str) -> Vec<Passport> {
+fn parse_data(input_data: &str) -> Result<Vec<Passport>, ParseError<Infallible>> {
     let mut ret = Vec::<Passport>::new();
-    for passport_block in input_data.split("\n\n") {
-        ret.push(Passport::parse(passport_block));
+    for passport_block in parsing::records(input_data) {
+        let passport = Passport::parse(passport_block.text)
+            .map_err(|err| err.offset_line(passport_block.line - 1))?;
+        ret.push(passport);
     }
-    ret
+    Ok(ret)
 }
 
 fn main() {
+    // Usage: day4 [--time | --bench] [--record]
+    let args = Args::parse(std::env::args().skip(1));
+    let mut timings = Timings::new(args.mode);
     let data = include_str!("../input_data.txt");
-    let passports = parse_data(data);
-    let valid_day_a = count_valid_day_a(&passports);
+    let mut answers = AnswerCheck::new(
+        env!("CARGO_MANIFEST_DIR"),
+        env!("CARGO_PKG_NAME"),
+        data,
+        args.record,
+    );
+    let passports = match timings.time("parse", || parse_data(data)) {
+        Ok(passports) => passports,
+        Err(err) => {
+            println!("Could not parse the passports: {}", err);
+            return;
+        }
+    };
+    let valid_day_a = timings.time("part a", || count_valid_day_a(&passports));
     println!("Day a result: {}", valid_day_a);
-    let valid_day_b = count_valid_day_b(&passports);
+    answers.check("a", valid_day_a);
+    let valid_day_b = timings.time("part b", || count_valid_day_b(&passports));
     println!("Day b result: {}", valid_day_b);
+    answers.check("b", valid_day_b);
+    timings.finish(env!("CARGO_MANIFEST_DIR"));
+    answers.finish();
 }
 
 #[cfg(test)]
@@ -170,11 +188,80 @@ mod test {
     use crate::count_valid_day_b;
     use crate::parse_data;
     use crate::PassportField;
+    use proptest::prelude::*;
+
+    const REQUIRED: [&str; 7] = ["byr", "iyr", "eyr", "hgt", "hcl", "ecl", "pid"];
+
+    fn in_range(val: &str, min: u32, max: u32) -> bool {
+        !val.is_empty()
+            && val.chars().all(|c| c.is_ascii_digit())
+            && val
+                .parse::<u32>()
+                .is_ok_and(|year| min <= year && year <= max)
+    }
+
+    // The day b rules written out directly, to test the real versions against
+    fn naive_valid_field(key: &str, val: &str) -> bool {
+        match key {
+            "byr" => in_range(val, 1920, 2002),
+            "iyr" => in_range(val, 2010, 2020),
+            "eyr" => in_range(val, 2020, 2030),
+            "hgt" => match (val.strip_suffix("cm"), val.strip_suffix("in")) {
+                (Some(cm), _) => in_range(cm, 150, 193),
+                (_, Some(inches)) => in_range(inches, 59, 76),
+                _ => false,
+            },
+            "hcl" => val.strip_prefix('#').is_some_and(|hex| {
+                hex.len() == 6 && hex.chars().all(|c| "0123456789abcdef".contains(c))
+            }),
+            "ecl" => ["amb", "blu", "brn", "gry", "grn", "hzl", "oth"].contains(&val),
+            "pid" => val.len() == 9 && val.chars().all(|c| c.is_ascii_digit()),
+            _ => true,
+        }
+    }
+
+    fn naive_valid(fields: &[(&str, String)]) -> (bool, bool) {
+        let has_all = REQUIRED
+            .iter()
+            .all(|key| fields.iter().any(|(field, _)| field == key));
+        let all_valid = fields.iter().all(|(key, val)| naive_valid_field(key, val));
+        (has_all, has_all && all_valid)
+    }
+
+    // Values close to the edges of each rule, along with some junk
+    fn value_strategy(key: &'static str) -> BoxedStrategy<String> {
+        let near_valid = match key {
+            "byr" | "iyr" | "eyr" => (1910..2040u32).prop_map(|year| year.to_string()).boxed(),
+            "hgt" => (50..200u32, prop::sample::select(vec!["cm", "in", ""]))
+                .prop_map(|(height, unit)| format!("{}{}", height, unit))
+                .boxed(),
+            "hcl" => "#?[0-9a-h]{5,7}".boxed(),
+            "ecl" => {
+                prop::sample::select(vec!["amb", "blu", "brn", "gry", "grn", "hzl", "oth", "wat"])
+                    .prop_map(String::from)
+                    .boxed()
+            }
+            "pid" => "[0-9]{8,10}".boxed(),
+            _ => "[0-9]{1,3}".boxed(),
+        };
+        prop_oneof![3 => near_valid, 1 => "[0-9a-z#]{1,8}"].boxed()
+    }
+
+    fn passport_strategy() -> impl Strategy<Value = Vec<(&'static str, String)>> {
+        let keys = vec!["byr", "iyr", "eyr", "hgt", "hcl", "ecl", "pid", "cid"];
+        prop::sample::subsequence(keys, 5..=8)
+            .prop_shuffle()
+            .prop_flat_map(|keys| {
+                keys.into_iter()
+                    .map(|key| (Just(key), value_strategy(key)))
+                    .collect::<Vec<_>>()
+            })
+    }
 
     #[test]
     fn test_parse() {
         let data = include_str!("../test_data.txt");
-        let ret = parse_data(data);
+        let ret = parse_data(data).unwrap();
         assert_eq!(ret.len(), 4);
         for (i, (key, val)) in [
             ("ecl", "gry"),
@@ -192,24 +279,37 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_parse_errors() {
+        let err = parse_data("byr:1937 iyr:2017\n\neyr:2020\nhgt 183cm").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Line 4, column 1: \"hgt\" is not in key:val format"
+        );
+        // A repeated key is kept rather than being an error
+        let ret = parse_data("byr:1937 byr:1937 iyr:2017 eyr:2020 hgt:183cm hcl:#fffffd ecl:gry")
+            .unwrap();
+        assert_eq!(ret[0].keys.len(), 7);
+    }
+
     #[test]
     fn test_validity_day_a() {
         let data = include_str!("../test_data.txt");
-        let ret = parse_data(data);
+        let ret = parse_data(data).unwrap();
         assert_eq!(count_valid_day_a(&ret), 2);
     }
 
     #[test]
     fn test_validity_day_b() {
         let data = include_str!("../valid_dayb_passports.txt");
-        let ret = parse_data(data);
+        let ret = parse_data(data).unwrap();
         assert_eq!(count_valid_day_b(&ret), 4);
     }
 
     #[test]
     fn test_invalidity_day_b() {
         let data = include_str!("../invalid_dayb_passports.txt");
-        let ret = parse_data(data);
+        let ret = parse_data(data).unwrap();
         assert_eq!(count_valid_day_b(&ret), 0);
     }
 
@@ -253,4 +353,50 @@ mod test {
             );
         }
     }
+
+    #[test]
+    fn test_generated() {
+        for seed in 0..10 {
+            let generated = generators::day4::generate(seed, 500);
+            let passports = parse_data(&generated.input).unwrap();
+            assert_eq!(count_valid_day_a(&passports), generated.part_a);
+            assert_eq!(count_valid_day_b(&passports), generated.part_b);
+        }
+        let mix = generators::day4::PassportMix {
+            missing_field: 0,
+            invalid_value: 200,
+            valid: 0,
+        };
+        let generated = generators::day4::generate_mix(1, mix);
+        let passports = parse_data(&generated.input).unwrap();
+        assert_eq!(count_valid_day_a(&passports), 200);
+        assert_eq!(count_valid_day_b(&passports), 0);
+    }
+
+    proptest! {
+        #[test]
+        fn test_matches_naive(passports in prop::collection::vec(passport_strategy(), 1..10)) {
+            let input = passports
+                .iter()
+                .map(|fields| {
+                    fields
+                        .iter()
+                        .map(|(key, val)| format!("{}:{}", key, val))
+                        .collect::<Vec<String>>()
+                        .join(" ")
+                })
+                .collect::<Vec<String>>()
+                .join("\n\n");
+            let parsed = parse_data(&input).unwrap();
+            let valid: Vec<(bool, bool)> = passports.iter().map(|fields| naive_valid(fields)).collect();
+            prop_assert_eq!(
+                count_valid_day_a(&parsed),
+                valid.iter().filter(|(day_a, _)| *day_a).count()
+            );
+            prop_assert_eq!(
+                count_valid_day_b(&parsed),
+                valid.iter().filter(|(_, day_b)| *day_b).count()
+            );
+        }
+    }
 }
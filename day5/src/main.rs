@@ -5,6 +5,10 @@
 //
 // We have to find the biggest seat_number, and the
 // seat_number not present in the full list
+#[cfg(test)]
+mod naive;
+
+use harness::{AnswerCheck, Args, Timings};
 
 struct PlaneTicket {
     column: u32,
@@ -45,36 +49,63 @@ impl PlaneTicket {
 
 fn calculate_day_a_answer(tickets: &[u32]) -> u32 {
     // find the max ticket
-    return *tickets.iter().max().expect("There is at least one max");
+    *tickets.iter().max().expect("There is at least one max")
 }
 
 fn calculate_day_b_answer(tickets: &[u32]) -> u32 {
-    // Find the missing ticket in the middle
+    // Find the missing ticket in the middle, marking every taken seat in a
+    // bitset first so each check is a lookup rather than a search.
+    // A parsed ticket is always below 1024 (128 rows of 8), but the bitset
+    // is sized from the highest ticket so any list of seats works.
     let min = tickets.iter().min().expect("There is at least one min");
     let max = tickets.iter().max().expect("There is at least one max");
-    for i in *min..*max {
-        if !tickets.contains(&i) {
-            return i;
-        }
+    let mut taken = vec![0_u64; *max as usize / 64 + 1];
+    for ticket in tickets.iter() {
+        taken[*ticket as usize / 64] |= 1 << (ticket % 64);
     }
-    0
+    (*min..*max)
+        .find(|i| taken[*i as usize / 64] & (1 << (i % 64)) == 0)
+        .unwrap_or(0)
 }
 
 fn main() {
+    // Usage: day5 [--time | --bench] [--record]
+    let args = Args::parse(std::env::args().skip(1));
+    let mut timings = Timings::new(args.mode);
     let input = include_str!("../input_data.txt");
-    let tickets = input
-        .lines()
-        .map(|line| PlaneTicket::parse(line).calc_seat())
-        .collect::<Vec<u32>>();
+    let mut answers = AnswerCheck::new(
+        env!("CARGO_MANIFEST_DIR"),
+        env!("CARGO_PKG_NAME"),
+        input,
+        args.record,
+    );
+    let tickets = timings.time("parse", || {
+        input
+            .lines()
+            .map(|line| PlaneTicket::parse(line).calc_seat())
+            .collect::<Vec<u32>>()
+    });
     let tickets_ref = &tickets[..];
 
-    println!("Day a answer: {}", calculate_day_a_answer(tickets_ref));
-    println!("Day b answer: {}", calculate_day_b_answer(tickets_ref));
+    let day_a = timings.time("part a", || calculate_day_a_answer(tickets_ref));
+    println!("Day a answer: {}", day_a);
+    answers.check("a", day_a);
+    let day_b = timings.time("part b", || calculate_day_b_answer(tickets_ref));
+    println!("Day b answer: {}", day_b);
+    answers.check("b", day_b);
+    timings.finish(env!("CARGO_MANIFEST_DIR"));
+    answers.finish();
 }
 
 #[cfg(test)]
 mod test {
-    use crate::PlaneTicket;
+    use crate::naive;
+    use crate::{calculate_day_a_answer, calculate_day_b_answer, PlaneTicket};
+    use proptest::prelude::*;
+
+    // How many seats there are, 128 rows of 8
+    const SEATS: usize = 1024;
+
     #[test]
     fn test_parse() {
         for (ticket_str, column, row) in [
@@ -101,4 +132,61 @@ mod test {
             assert_eq!(plane_ticket.calc_seat(), seat_number)
         }
     }
+
+    #[test]
+    fn test_generated() {
+        for (seed, size) in [(1, 2), (2, 10), (3, 500), (4, 1023)] {
+            let generated = generators::day5::generate(seed, size);
+            let tickets: Vec<u32> = generated
+                .input
+                .lines()
+                .map(|line| PlaneTicket::parse(line).calc_seat())
+                .collect();
+            assert_eq!(calculate_day_a_answer(&tickets), generated.part_a);
+            assert_eq!(calculate_day_b_answer(&tickets), generated.part_b);
+        }
+    }
+
+    #[test]
+    fn test_missing_seat() {
+        assert_eq!(calculate_day_b_answer(&[5, 3, 7, 6]), 4);
+        assert_eq!(calculate_day_b_answer(&[64, 62, 63, 66, 65, 67]), 0);
+        assert_eq!(calculate_day_b_answer(&[0, 1023]), 1);
+        assert_eq!(calculate_day_b_answer(&[9]), 0);
+        // Past the end of the plane still works
+        assert_eq!(calculate_day_b_answer(&[5000, 5002, 5001, 5004]), 5003);
+    }
+
+    // A full run of seats from `first`, with the seat `gap` along left out
+    fn seats_with_gap() -> impl Strategy<Value = Vec<u32>> {
+        (0..SEATS as u32 - 2, 3..SEATS as u32)
+            .prop_flat_map(|(first, length)| {
+                let length = length.min(SEATS as u32 - first);
+                (Just(first), Just(length), 1..length - 1)
+            })
+            .prop_map(|(first, length, gap)| {
+                (first..first + length)
+                    .filter(|seat| *seat != first + gap)
+                    .collect()
+            })
+            .prop_shuffle()
+    }
+
+    proptest! {
+        #[test]
+        fn test_matches_naive(tickets in prop::collection::vec(0..SEATS as u32, 1..300)) {
+            prop_assert_eq!(
+                calculate_day_b_answer(&tickets),
+                naive::calculate_day_b_answer(&tickets)
+            );
+        }
+
+        #[test]
+        fn test_matches_naive_with_gap(tickets in seats_with_gap()) {
+            prop_assert_eq!(
+                calculate_day_b_answer(&tickets),
+                naive::calculate_day_b_answer(&tickets)
+            );
+        }
+    }
 }
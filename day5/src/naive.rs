@@ -0,0 +1,14 @@
+// The original implementation, searching the whole list for every seat.
+// Kept around as a reference for the property tests against the bitset version.
+
+pub fn calculate_day_b_answer(tickets: &[u32]) -> u32 {
+    // Find the missing ticket in the middle
+    let min = tickets.iter().min().expect("There is at least one min");
+    let max = tickets.iter().max().expect("There is at least one max");
+    for i in *min..*max {
+        if !tickets.contains(&i) {
+            return i;
+        }
+    }
+    0
+}
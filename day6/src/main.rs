@@ -1,54 +1,324 @@
-use std::collections::HashSet;
+mod statistics;
+
+use harness::{AnswerCheck, Mode, Timings};
+use statistics::{CsvTable, SurveyStatistics};
+use std::ops::{BitAnd, BitOr, BitXor, Sub};
+
+/// The most questions a single `AnswerSet` bitmask can hold.
+const MAX_QUESTIONS: usize = 32;
+
+#[derive(Debug, PartialEq)]
+enum CustomsFormError {
+    /// A character on a form that isn't one of the questions (1-indexed line)
+    InvalidAnswer {
+        line: usize,
+        answer: char,
+    },
+    DuplicateQuestion(char),
+    TooManyQuestions(usize),
+}
+
+impl std::fmt::Display for CustomsFormError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CustomsFormError::InvalidAnswer { line, answer } => {
+                write!(f, "Line {}: {:?} is not one of the questions", line, answer)
+            }
+            CustomsFormError::DuplicateQuestion(question) => {
+                write!(f, "{:?} is in the question alphabet twice", question)
+            }
+            CustomsFormError::TooManyQuestions(count) => write!(
+                f,
+                "{} questions is more than the {} allowed",
+                count, MAX_QUESTIONS
+            ),
+        }
+    }
+}
+
+impl CustomsFormError {
+    fn offset_line(self, offset: usize) -> CustomsFormError {
+        match self {
+            CustomsFormError::InvalidAnswer { line, answer } => CustomsFormError::InvalidAnswer {
+                line: line + offset,
+                answer,
+            },
+            err => err,
+        }
+    }
+}
+
+/// The set of questions answered "yes", stored as one bit per question in the
+/// order of the `QuestionAlphabet` (so by default 'a' is bit 0 through to 'z'
+/// as bit 25).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct AnswerSet(u32);
+
+impl AnswerSet {
+    const EMPTY: AnswerSet = AnswerSet(0);
+    const ALL: AnswerSet = AnswerSet(u32::MAX);
+
+    fn contains(&self, question: usize) -> bool {
+        self.0 & (1 << question) != 0
+    }
+
+    fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+}
+
+/// The characters that are allowed to appear on a form, each one being a
+/// question that can be answered.
+#[derive(Debug)]
+struct QuestionAlphabet {
+    questions: Vec<char>,
+}
+
+impl Default for QuestionAlphabet {
+    fn default() -> QuestionAlphabet {
+        QuestionAlphabet {
+            questions: ('a'..='z').collect(),
+        }
+    }
+}
+
+impl QuestionAlphabet {
+    fn new(questions: &str) -> Result<QuestionAlphabet, CustomsFormError> {
+        let questions: Vec<char> = questions.chars().collect();
+        if questions.len() > MAX_QUESTIONS {
+            return Err(CustomsFormError::TooManyQuestions(questions.len()));
+        }
+        for (i, question) in questions.iter().enumerate() {
+            if questions[..i].contains(question) {
+                return Err(CustomsFormError::DuplicateQuestion(*question));
+            }
+        }
+        Ok(QuestionAlphabet { questions })
+    }
+
+    /// Convert a single form into its answers, failing on the first character
+    /// that isn't a question.
+    fn answer_set(&self, customs_form: &str) -> Result<AnswerSet, char> {
+        customs_form.chars().try_fold(AnswerSet::EMPTY, |set, c| {
+            match self.questions.iter().position(|question| *question == c) {
+                Some(question) => Ok(set | AnswerSet(1 << question)),
+                None => Err(c),
+            }
+        })
+    }
+}
+
+impl BitOr for AnswerSet {
+    type Output = AnswerSet;
+    fn bitor(self, other: AnswerSet) -> AnswerSet {
+        AnswerSet(self.0 | other.0)
+    }
+}
+
+impl BitAnd for AnswerSet {
+    type Output = AnswerSet;
+    fn bitand(self, other: AnswerSet) -> AnswerSet {
+        AnswerSet(self.0 & other.0)
+    }
+}
+
+impl BitXor for AnswerSet {
+    type Output = AnswerSet;
+    fn bitxor(self, other: AnswerSet) -> AnswerSet {
+        AnswerSet(self.0 ^ other.0)
+    }
+}
+
+impl Sub for AnswerSet {
+    type Output = AnswerSet;
+    fn sub(self, other: AnswerSet) -> AnswerSet {
+        AnswerSet(self.0 & !other.0)
+    }
+}
+
+/// An expression over the answers of everyone in a group. The leaves pick out
+/// questions by how many members answered them, and they can be combined with
+/// `|`, `&`, `^` and `-` (set difference) into larger queries, e.g.
+/// `GroupQuery::AtLeast(2) - GroupQuery::All` for questions most, but not all,
+/// of the group answered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum GroupQuery {
+    /// Answered by anyone in the group (union)
+    Any,
+    /// Answered by everyone in the group (intersection)
+    All,
+    /// Answered by at least k members of the group
+    AtLeast(usize),
+    /// Answered by exactly one member of the group
+    ExactlyOne,
+    /// Answered by an odd number of members (symmetric difference)
+    SymmetricDifference,
+    /// Matched by either query
+    Or(Box<GroupQuery>, Box<GroupQuery>),
+    /// Matched by both queries
+    And(Box<GroupQuery>, Box<GroupQuery>),
+    /// Matched by exactly one of the queries
+    Xor(Box<GroupQuery>, Box<GroupQuery>),
+    /// Matched by the first query but not the second
+    Minus(Box<GroupQuery>, Box<GroupQuery>),
+}
+
+impl BitOr for GroupQuery {
+    type Output = GroupQuery;
+    fn bitor(self, other: GroupQuery) -> GroupQuery {
+        GroupQuery::Or(Box::new(self), Box::new(other))
+    }
+}
+
+impl BitAnd for GroupQuery {
+    type Output = GroupQuery;
+    fn bitand(self, other: GroupQuery) -> GroupQuery {
+        GroupQuery::And(Box::new(self), Box::new(other))
+    }
+}
+
+impl BitXor for GroupQuery {
+    type Output = GroupQuery;
+    fn bitxor(self, other: GroupQuery) -> GroupQuery {
+        GroupQuery::Xor(Box::new(self), Box::new(other))
+    }
+}
+
+impl Sub for GroupQuery {
+    type Output = GroupQuery;
+    fn sub(self, other: GroupQuery) -> GroupQuery {
+        GroupQuery::Minus(Box::new(self), Box::new(other))
+    }
+}
+
+/// Everything needed to answer a `GroupQuery`, gathered in a single pass over
+/// the group's forms.
+#[derive(Debug)]
+struct GroupSummary {
+    union: AnswerSet,
+    intersection: AnswerSet,
+    symmetric_difference: AnswerSet,
+    counts: [usize; MAX_QUESTIONS],
+}
+
+impl GroupSummary {
+    fn new(answers: &[AnswerSet]) -> GroupSummary {
+        let mut summary = GroupSummary {
+            union: AnswerSet::EMPTY,
+            intersection: AnswerSet::ALL,
+            symmetric_difference: AnswerSet::EMPTY,
+            counts: [0; MAX_QUESTIONS],
+        };
+        for answers in answers.iter().copied() {
+            summary.union = summary.union | answers;
+            summary.intersection = summary.intersection & answers;
+            summary.symmetric_difference = summary.symmetric_difference ^ answers;
+            for (question, count) in summary.counts.iter_mut().enumerate() {
+                if answers.contains(question) {
+                    *count += 1;
+                }
+            }
+        }
+        if answers.is_empty() {
+            summary.intersection = AnswerSet::EMPTY;
+        }
+        summary
+    }
+
+    fn evaluate(&self, query: &GroupQuery) -> AnswerSet {
+        match query {
+            GroupQuery::Any => self.union,
+            GroupQuery::All => self.intersection,
+            GroupQuery::SymmetricDifference => self.symmetric_difference,
+            GroupQuery::ExactlyOne => self.with_count(|count| count == 1),
+            GroupQuery::AtLeast(k) => self.with_count(|count| count >= *k),
+            GroupQuery::Or(a, b) => self.evaluate(a) | self.evaluate(b),
+            GroupQuery::And(a, b) => self.evaluate(a) & self.evaluate(b),
+            GroupQuery::Xor(a, b) => self.evaluate(a) ^ self.evaluate(b),
+            GroupQuery::Minus(a, b) => self.evaluate(a) - self.evaluate(b),
+        }
+    }
+
+    /// Questions whose answer count matches the predicate. Only questions
+    /// someone actually answered are considered, so `AtLeast(0)` is the same
+    /// as `AtLeast(1)`.
+    fn with_count(&self, predicate: impl Fn(usize) -> bool) -> AnswerSet {
+        let matching = self
+            .counts
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| predicate(**count))
+            .fold(AnswerSet::EMPTY, |set, (question, _)| {
+                set | AnswerSet(1 << question)
+            });
+        matching & self.union
+    }
+}
+
+/// The forms of a single group. A group with no members has answered no
+/// questions, so every query on it is empty (including `GroupQuery::All`).
+/// The summary is worked out once when the group is built, so any number of
+/// queries can be run against it without going back over the forms.
 struct CustomFormGroup {
     customs_forms: Vec<String>,
+    summary: GroupSummary,
 }
 impl CustomFormGroup {
-    fn new(lines: &[&str]) -> CustomFormGroup {
+    fn new(
+        lines: &[&str],
+        alphabet: &QuestionAlphabet,
+    ) -> Result<CustomFormGroup, CustomsFormError> {
         let mut customs_forms = Vec::<String>::new();
-        for line in lines {
-            customs_forms.push(String::from(*line));
+        let mut answers = Vec::<AnswerSet>::new();
+        for (i, line) in lines.iter().enumerate() {
+            let line = line
+                .trim_end_matches(|c: char| c.is_whitespace() && !alphabet.questions.contains(&c));
+            answers.push(alphabet.answer_set(line).map_err(|answer| {
+                CustomsFormError::InvalidAnswer {
+                    line: i + 1,
+                    answer,
+                }
+            })?);
+            customs_forms.push(String::from(line));
         }
-        CustomFormGroup { customs_forms }
+        Ok(CustomFormGroup {
+            customs_forms,
+            summary: GroupSummary::new(&answers[..]),
+        })
+    }
+
+    fn members(&self) -> usize {
+        self.customs_forms.len()
+    }
+
+    fn count_answers(&self, query: &GroupQuery) -> usize {
+        self.summary.evaluate(query).len()
     }
 
     fn calc_a_any_answer(&self) -> usize {
-        let mut unique_keys = HashSet::<char>::new();
-        for customs_form in self.customs_forms.iter() {
-            let current_hash: HashSet<char> = customs_form.chars().collect();
-            let union = unique_keys.union(&current_hash);
-            unique_keys = union.into_iter().cloned().collect();
-        }
-        unique_keys.len()
+        self.count_answers(&GroupQuery::Any)
     }
 
     fn calc_b_all_answered(&self) -> usize {
-        let mut unique_keys: HashSet<char> = self.customs_forms[0].chars().collect();
-        for customs_form in self.customs_forms.iter() {
-            let current_hash: HashSet<char> = customs_form.chars().collect();
-            unique_keys = unique_keys
-                .intersection(&current_hash)
-                .into_iter()
-                .cloned()
-                .collect();
-        }
-        unique_keys.len()
+        self.count_answers(&GroupQuery::All)
     }
 }
 
-fn parse_input_into_groups(input_data: &str) -> Vec<CustomFormGroup> {
-    let lines = &input_data.lines().collect::<Vec<&str>>()[..];
+/// Split the input into groups of forms. Groups are separated by blank (or
+/// whitespace only) lines, and any number of blank lines between, before or
+/// after groups is ignored rather than making empty groups.
+fn parse_input_into_groups(
+    input_data: &str,
+    alphabet: &QuestionAlphabet,
+) -> Result<Vec<CustomFormGroup>, CustomsFormError> {
     let mut ret = Vec::<CustomFormGroup>::new();
-    let mut start = 0;
-    for (i, line) in lines.iter().enumerate() {
-        if line.is_empty() {
-            let group = CustomFormGroup::new(&lines[start..i]);
-            ret.push(group);
-            start = i + 1;
-        }
+    for record in parsing::records(input_data) {
+        let lines: Vec<&str> = record.lines().collect();
+        let group = CustomFormGroup::new(&lines[..], alphabet)
+            .map_err(|err| err.offset_line(record.line - 1))?;
+        ret.push(group);
     }
-    let group = CustomFormGroup::new(&lines[start..]);
-    ret.push(group);
-    ret
+    Ok(ret)
 }
 
 fn calculate_day_a_answer(customs_form_groups: &[CustomFormGroup]) -> usize {
@@ -58,6 +328,21 @@ fn calculate_day_a_answer(customs_form_groups: &[CustomFormGroup]) -> usize {
         .sum()
 }
 
+/// The total for each query over every group, visiting each group once and
+/// running all of the queries against its summary.
+fn calculate_query_answers(
+    customs_form_groups: &[CustomFormGroup],
+    queries: &[GroupQuery],
+) -> Vec<usize> {
+    let mut totals = vec![0; queries.len()];
+    for customs_form_group in customs_form_groups.iter() {
+        for (total, query) in totals.iter_mut().zip(queries.iter()) {
+            *total += customs_form_group.count_answers(query);
+        }
+    }
+    totals
+}
+
 fn calculate_day_b_answer(customs_form_groups: &[CustomFormGroup]) -> usize {
     customs_form_groups
         .iter()
@@ -65,23 +350,148 @@ fn calculate_day_b_answer(customs_form_groups: &[CustomFormGroup]) -> usize {
         .sum()
 }
 
+const USAGE: &str = "Usage: day6 [--questions QUESTIONS] [--stats] [--csv questions|groups] \
+                     [--time | --bench] [--record]";
+
+// Print what went wrong and exit with a failure
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
+
 fn main() {
     let input_data = include_str!("../input_data.txt");
-    let groups = parse_input_into_groups(input_data);
-    let calc_day_a = calculate_day_a_answer(&groups[..]);
+    // See `USAGE`. The allowed questions default to a-z.
+    let mut alphabet = QuestionAlphabet::default();
+    let mut show_stats = false;
+    let mut csv_table = None;
+    let mut mode = Mode::Off;
+    let mut record = false;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "--questions" => {
+                let questions = args
+                    .next()
+                    .unwrap_or_else(|| fail("--questions needs the questions, e.g. abc"));
+                alphabet = QuestionAlphabet::new(&questions)
+                    .unwrap_or_else(|err| fail(&format!("Invalid question alphabet: {}", err)));
+            }
+            "--stats" => show_stats = true,
+            "--csv" => {
+                let table = args
+                    .next()
+                    .unwrap_or_else(|| fail("--csv needs a table, questions or groups"));
+                csv_table = Some(table.parse::<CsvTable>().unwrap_or_else(|err| fail(&err)));
+            }
+            "--time" | "--bench" => mode = Mode::from_arg(&arg).unwrap(),
+            "--record" => record = true,
+            other => fail(&format!("Unknown argument {:?}\n{}", other, USAGE)),
+        }
+    }
+    let mut timings = Timings::new(mode);
+    let mut answers = AnswerCheck::new(
+        env!("CARGO_MANIFEST_DIR"),
+        env!("CARGO_PKG_NAME"),
+        input_data,
+        record,
+    );
+    let groups = timings
+        .time("parse", || parse_input_into_groups(input_data, &alphabet))
+        .unwrap_or_else(|err| fail(&format!("Could not parse the forms: {}", err)));
+    let calc_day_a = timings.time("part a", || calculate_day_a_answer(&groups[..]));
     println!("Day a answer: {}", calc_day_a);
-    let calc_day_b = calculate_day_b_answer(&groups[..]);
+    answers.check("a", calc_day_a);
+    let calc_day_b = timings.time("part b", || calculate_day_b_answer(&groups[..]));
     println!("Day b answer: {}", calc_day_b);
+    answers.check("b", calc_day_b);
+    let queries = [
+        GroupQuery::AtLeast(2),
+        GroupQuery::ExactlyOne,
+        GroupQuery::SymmetricDifference,
+        GroupQuery::AtLeast(2) - GroupQuery::All,
+    ];
+    let totals = calculate_query_answers(&groups[..], &queries[..]);
+    for (query, total) in queries.iter().zip(totals) {
+        println!("{:?} answer: {}", query, total);
+    }
+    if show_stats || csv_table.is_some() {
+        let stats = SurveyStatistics::new(&groups[..], &alphabet);
+        if show_stats {
+            println!("{}", stats);
+        }
+        if let Some(table) = csv_table {
+            print!("{}", stats.csv(table));
+        }
+    }
+    timings.finish(env!("CARGO_MANIFEST_DIR"));
+    answers.finish();
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use proptest::prelude::*;
+    use std::collections::HashMap;
+
+    fn calculate_query_answer(
+        customs_form_groups: &[CustomFormGroup],
+        query: &GroupQuery,
+    ) -> usize {
+        calculate_query_answers(customs_form_groups, std::slice::from_ref(query))[0]
+    }
+
+    // Whether a question answered by `count` of a group's `members` matches
+    // the query, ignoring questions nobody answered
+    fn naive_matches(query: &GroupQuery, count: usize, members: usize) -> bool {
+        match query {
+            GroupQuery::Any => true,
+            GroupQuery::All => count == members,
+            GroupQuery::AtLeast(k) => count >= *k,
+            GroupQuery::ExactlyOne => count == 1,
+            GroupQuery::SymmetricDifference => count % 2 == 1,
+            GroupQuery::Or(a, b) => {
+                naive_matches(a, count, members) || naive_matches(b, count, members)
+            }
+            GroupQuery::And(a, b) => {
+                naive_matches(a, count, members) && naive_matches(b, count, members)
+            }
+            GroupQuery::Xor(a, b) => {
+                naive_matches(a, count, members) != naive_matches(b, count, members)
+            }
+            GroupQuery::Minus(a, b) => {
+                naive_matches(a, count, members) && !naive_matches(b, count, members)
+            }
+        }
+    }
+
+    // How many questions in each group match a query, counting each
+    // question's answers one form at a time
+    fn naive_count(groups: &[Vec<String>], query: &GroupQuery) -> usize {
+        let mut total = 0;
+        for group in groups.iter() {
+            let mut counts = HashMap::<char, usize>::new();
+            for form in group.iter() {
+                let mut seen = vec![];
+                for c in form.chars() {
+                    if !seen.contains(&c) {
+                        seen.push(c);
+                        *counts.entry(c).or_insert(0) += 1;
+                    }
+                }
+            }
+            total += counts
+                .values()
+                .filter(|count| naive_matches(query, **count, group.len()))
+                .count();
+        }
+        total
+    }
 
     #[test]
     fn test_parse_groups() {
         let test_data = include_str!("../test_data.txt");
-        let groups = parse_input_into_groups(test_data);
+        let groups = parse_input_into_groups(test_data, &QuestionAlphabet::default()).unwrap();
         assert_eq!(groups.len(), 5);
         assert_eq!(groups[0].customs_forms.len(), 1);
         assert_eq!(groups[0].customs_forms[0], "abc");
@@ -104,7 +514,7 @@ mod test {
     #[test]
     fn test_calc_day_a_individually() {
         let test_data = include_str!("../test_data.txt");
-        let groups = parse_input_into_groups(test_data);
+        let groups = parse_input_into_groups(test_data, &QuestionAlphabet::default()).unwrap();
         for (i, answer) in [3, 3, 3, 1, 1].iter().enumerate() {
             assert_eq!(groups[i].calc_a_any_answer(), *answer);
         }
@@ -113,14 +523,14 @@ mod test {
     #[test]
     fn test_calc_day_a_total() {
         let test_data = include_str!("../test_data.txt");
-        let groups = parse_input_into_groups(test_data);
+        let groups = parse_input_into_groups(test_data, &QuestionAlphabet::default()).unwrap();
         assert_eq!(calculate_day_a_answer(&groups[..]), 11);
     }
 
     #[test]
     fn test_calc_day_b_individually() {
         let test_data = include_str!("../test_data.txt");
-        let groups = parse_input_into_groups(test_data);
+        let groups = parse_input_into_groups(test_data, &QuestionAlphabet::default()).unwrap();
         for (i, answer) in [3, 0, 1, 1, 1].iter().enumerate() {
             assert_eq!(groups[i].calc_b_all_answered(), *answer);
         }
@@ -129,7 +539,207 @@ mod test {
     #[test]
     fn test_calc_day_b_total() {
         let test_data = include_str!("../test_data.txt");
-        let groups = parse_input_into_groups(test_data);
+        let groups = parse_input_into_groups(test_data, &QuestionAlphabet::default()).unwrap();
         assert_eq!(calculate_day_b_answer(&groups[..]), 6);
     }
+
+    #[test]
+    fn test_calc_query_totals() {
+        let test_data = include_str!("../test_data.txt");
+        let groups = parse_input_into_groups(test_data, &QuestionAlphabet::default()).unwrap();
+        assert_eq!(calculate_query_answer(&groups[..], &GroupQuery::Any), 11);
+        assert_eq!(calculate_query_answer(&groups[..], &GroupQuery::All), 6);
+        assert_eq!(
+            calculate_query_answer(&groups[..], &GroupQuery::AtLeast(2)),
+            2
+        );
+        assert_eq!(
+            calculate_query_answer(&groups[..], &GroupQuery::ExactlyOne),
+            9
+        );
+    }
+
+    fn answers(form: &str) -> AnswerSet {
+        QuestionAlphabet::default().answer_set(form).unwrap()
+    }
+
+    #[test]
+    fn test_answer_set() {
+        let answer_set = answers("abz");
+        assert_eq!(answer_set, AnswerSet(1 | 2 | 1 << 25));
+        assert_eq!(answer_set.len(), 3);
+        assert!(answer_set.contains(25));
+        assert!(!answer_set.contains(2));
+        assert_eq!(answers("aab"), answers("ba"));
+    }
+
+    #[test]
+    fn test_group_queries() {
+        let group =
+            CustomFormGroup::new(&["abc", "abd", "ae"], &QuestionAlphabet::default()).unwrap();
+        assert_eq!(group.members(), 3);
+        let summary = &group.summary;
+        assert_eq!(summary.evaluate(&GroupQuery::Any), answers("abcde"));
+        assert_eq!(summary.evaluate(&GroupQuery::All), answers("a"));
+        assert_eq!(summary.evaluate(&GroupQuery::AtLeast(2)), answers("ab"));
+        assert_eq!(
+            summary.evaluate(&GroupQuery::AtLeast(1)),
+            summary.evaluate(&GroupQuery::Any)
+        );
+        assert_eq!(summary.evaluate(&GroupQuery::ExactlyOne), answers("cde"));
+        assert_eq!(
+            summary.evaluate(&GroupQuery::SymmetricDifference),
+            answers("acde")
+        );
+    }
+
+    #[test]
+    fn test_composed_queries() {
+        let group =
+            CustomFormGroup::new(&["abc", "abd", "ae"], &QuestionAlphabet::default()).unwrap();
+        let summary = &group.summary;
+        assert_eq!(
+            summary.evaluate(&(GroupQuery::AtLeast(2) - GroupQuery::All)),
+            answers("b")
+        );
+        assert_eq!(
+            summary.evaluate(&(GroupQuery::All | GroupQuery::ExactlyOne)),
+            answers("acde")
+        );
+        assert_eq!(
+            summary.evaluate(&(GroupQuery::SymmetricDifference & GroupQuery::AtLeast(2))),
+            answers("a")
+        );
+        assert_eq!(
+            summary.evaluate(&(GroupQuery::Any ^ GroupQuery::ExactlyOne)),
+            answers("ab")
+        );
+        let groups = [group];
+        assert_eq!(
+            calculate_query_answers(
+                &groups[..],
+                &[GroupQuery::Any, GroupQuery::All - GroupQuery::Any]
+            ),
+            [5, 0]
+        );
+    }
+
+    #[test]
+    fn test_empty_group() {
+        let group = CustomFormGroup::new(&[], &QuestionAlphabet::default()).unwrap();
+        assert_eq!(group.calc_a_any_answer(), 0);
+        assert_eq!(group.calc_b_all_answered(), 0);
+        assert_eq!(group.count_answers(&GroupQuery::AtLeast(0)), 0);
+    }
+
+    #[test]
+    fn test_parse_extra_blank_lines() {
+        let alphabet = QuestionAlphabet::default();
+        let groups = parse_input_into_groups("\n\nab\nac\n\n\n  \nb\n\n", &alphabet).unwrap();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].customs_forms, ["ab", "ac"]);
+        assert_eq!(groups[1].customs_forms, ["b"]);
+        assert_eq!(calculate_day_b_answer(&groups[..]), 2);
+        assert_eq!(parse_input_into_groups("", &alphabet).unwrap().len(), 0);
+        assert_eq!(parse_input_into_groups("\n\n", &alphabet).unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_parse_trailing_whitespace() {
+        let groups =
+            parse_input_into_groups("ab \r\nb\t\r\n", &QuestionAlphabet::default()).unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].customs_forms, ["ab", "b"]);
+        assert_eq!(groups[0].calc_a_any_answer(), 2);
+        assert_eq!(groups[0].calc_b_all_answered(), 1);
+        let alphabet = QuestionAlphabet::new("ab ").unwrap();
+        let groups = parse_input_into_groups("ab \r\nb\t\n", &alphabet).unwrap();
+        assert_eq!(groups[0].customs_forms, ["ab ", "b"]);
+        assert_eq!(groups[0].calc_a_any_answer(), 3);
+    }
+
+    #[test]
+    fn test_parse_stray_characters() {
+        let alphabet = QuestionAlphabet::default();
+        for (input, line, answer) in [
+            ("ab\na b", 2, ' '),
+            ("ab\n\nc\nA", 4, 'A'),
+            ("a1", 1, '1'),
+            ("\n\na\nb\n\nc\n\nd?", 8, '?'),
+        ] {
+            assert_eq!(
+                parse_input_into_groups(input, &alphabet).err(),
+                Some(CustomsFormError::InvalidAnswer { line, answer }),
+                "Expected {:?} to fail",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_custom_alphabet() {
+        let alphabet = QuestionAlphabet::new("xyz?").unwrap();
+        assert_eq!(alphabet.questions.len(), 4);
+        let groups = parse_input_into_groups("x?\nyx\n\nz", &alphabet).unwrap();
+        assert_eq!(calculate_day_a_answer(&groups[..]), 4);
+        assert_eq!(calculate_day_b_answer(&groups[..]), 2);
+        assert_eq!(
+            parse_input_into_groups("a", &alphabet).err(),
+            Some(CustomsFormError::InvalidAnswer {
+                line: 1,
+                answer: 'a'
+            })
+        );
+        assert_eq!(
+            QuestionAlphabet::new("abca").err(),
+            Some(CustomsFormError::DuplicateQuestion('a'))
+        );
+        assert_eq!(
+            QuestionAlphabet::new(&"x".repeat(33)).err(),
+            Some(CustomsFormError::TooManyQuestions(33))
+        );
+    }
+
+    #[test]
+    fn test_generated() {
+        let alphabet = QuestionAlphabet::default();
+        for seed in 0..10 {
+            let generated = generators::day6::generate(seed, 500);
+            let groups = parse_input_into_groups(&generated.input, &alphabet).unwrap();
+            assert_eq!(groups.len(), 500);
+            assert_eq!(calculate_day_a_answer(&groups[..]), generated.part_a);
+            assert_eq!(calculate_day_b_answer(&groups[..]), generated.part_b);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_matches_naive(
+            groups in prop::collection::vec(prop::collection::vec("[a-f]{1,6}", 1..5), 1..10)
+        ) {
+            let input = groups
+                .iter()
+                .map(|group| group.join("\n"))
+                .collect::<Vec<String>>()
+                .join("\n\n");
+            let parsed = parse_input_into_groups(&input, &QuestionAlphabet::default()).unwrap();
+            prop_assert_eq!(calculate_day_a_answer(&parsed[..]), naive_count(&groups, &GroupQuery::Any));
+            prop_assert_eq!(calculate_day_b_answer(&parsed[..]), naive_count(&groups, &GroupQuery::All));
+            for query in [
+                GroupQuery::AtLeast(2),
+                GroupQuery::AtLeast(3),
+                GroupQuery::ExactlyOne,
+                GroupQuery::SymmetricDifference,
+                GroupQuery::AtLeast(2) - GroupQuery::All,
+                (GroupQuery::ExactlyOne | GroupQuery::All) ^ GroupQuery::SymmetricDifference,
+            ] {
+                prop_assert_eq!(
+                    calculate_query_answer(&parsed[..], &query),
+                    naive_count(&groups, &query),
+                    "{:?}",
+                    query
+                );
+            }
+        }
+    }
 }
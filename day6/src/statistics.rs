@@ -0,0 +1,263 @@
+// Statistics over the whole survey, rather than just the two sums the puzzle asks for.
+use crate::{CustomFormGroup, GroupQuery, QuestionAlphabet};
+use std::collections::BTreeMap;
+
+const HISTOGRAM_WIDTH: usize = 50;
+
+/// Which of the tables `SurveyStatistics::csv` writes. They have different
+/// columns, so only one goes in a single CSV file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsvTable {
+    Questions,
+    Groups,
+}
+
+impl std::str::FromStr for CsvTable {
+    type Err = String;
+    fn from_str(table: &str) -> Result<CsvTable, String> {
+        match table {
+            "questions" => Ok(CsvTable::Questions),
+            "groups" => Ok(CsvTable::Groups),
+            _ => Err(format!("Unknown CSV table {:?}", table)),
+        }
+    }
+}
+
+// A text field for CSV, always quoted (with any quotes doubled) so questions
+// like ',', '"' or ' ' survive the trip
+fn csv_text(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "\"\""))
+}
+
+#[derive(Debug)]
+pub struct SurveyStatistics {
+    questions: Vec<char>,
+    /// How many people answered each question, in alphabet order
+    answer_frequency: Vec<usize>,
+    /// How many groups had anyone answer each question, in alphabet order
+    group_frequency: Vec<usize>,
+    /// Group size -> number of groups of that size
+    group_sizes: BTreeMap<usize, usize>,
+    /// Per group: questions everyone answered / questions anyone answered.
+    /// None for groups where nobody answered anything.
+    agreement: Vec<Option<f64>>,
+}
+
+impl SurveyStatistics {
+    pub fn new(groups: &[CustomFormGroup], alphabet: &QuestionAlphabet) -> SurveyStatistics {
+        let question_count = alphabet.questions.len();
+        let mut answer_frequency = vec![0; question_count];
+        let mut group_frequency = vec![0; question_count];
+        let mut group_sizes = BTreeMap::<usize, usize>::new();
+        let mut agreement = Vec::<Option<f64>>::new();
+        for group in groups.iter() {
+            let summary = &group.summary;
+            for question in 0..question_count {
+                answer_frequency[question] += summary.counts[question];
+                if summary.counts[question] > 0 {
+                    group_frequency[question] += 1;
+                }
+            }
+            *group_sizes.entry(group.members()).or_insert(0) += 1;
+            let any = summary.evaluate(&GroupQuery::Any).len();
+            let all = summary.evaluate(&GroupQuery::All).len();
+            agreement.push(match any {
+                0 => None,
+                any => Some(all as f64 / any as f64),
+            });
+        }
+        SurveyStatistics {
+            questions: alphabet.questions.clone(),
+            answer_frequency,
+            group_frequency,
+            group_sizes,
+            agreement,
+        }
+    }
+
+    /// The questions with the highest answer frequency (all of them on a tie)
+    pub fn most_common(&self) -> Vec<char> {
+        match self.answer_frequency.iter().max() {
+            Some(max) => self.questions_with_frequency(*max),
+            None => vec![],
+        }
+    }
+
+    /// The questions with the lowest answer frequency (all of them on a tie)
+    pub fn least_common(&self) -> Vec<char> {
+        match self.answer_frequency.iter().min() {
+            Some(min) => self.questions_with_frequency(*min),
+            None => vec![],
+        }
+    }
+
+    fn questions_with_frequency(&self, frequency: usize) -> Vec<char> {
+        self.questions
+            .iter()
+            .zip(self.answer_frequency.iter())
+            .filter(|(_, count)| **count == frequency)
+            .map(|(question, _)| *question)
+            .collect()
+    }
+
+    /// The mean agreement ratio over every group that answered anything
+    pub fn mean_agreement(&self) -> Option<f64> {
+        let ratios: Vec<f64> = self.agreement.iter().flatten().copied().collect();
+        match ratios.len() {
+            0 => None,
+            len => Some(ratios.iter().sum::<f64>() / len as f64),
+        }
+    }
+
+    /// A horizontal bar chart of how many people answered each question,
+    /// scaled so the most common question fills the full width.
+    pub fn histogram(&self) -> String {
+        let max = self.answer_frequency.iter().max().copied().unwrap_or(0);
+        let mut ret = String::new();
+        for (question, count) in self.questions.iter().zip(self.answer_frequency.iter()) {
+            let bar_length = match max {
+                0 => 0,
+                max => count * HISTOGRAM_WIDTH / max,
+            };
+            ret += &format!("{} | {} {}\n", question, "#".repeat(bar_length), count);
+        }
+        ret
+    }
+
+    pub fn csv(&self, table: CsvTable) -> String {
+        match table {
+            CsvTable::Questions => self.questions_csv(),
+            CsvTable::Groups => self.groups_csv(),
+        }
+    }
+
+    /// One row per question: the question, people answering it, and groups answering it
+    fn questions_csv(&self) -> String {
+        let mut ret = String::from("question,people,groups\n");
+        for (i, question) in self.questions.iter().enumerate() {
+            ret += &format!(
+                "{},{},{}\n",
+                csv_text(&question.to_string()),
+                self.answer_frequency[i],
+                self.group_frequency[i]
+            );
+        }
+        ret
+    }
+
+    /// One row per group: its index and agreement ratio (empty if it answered nothing)
+    fn groups_csv(&self) -> String {
+        let mut ret = String::from("group,agreement\n");
+        for (i, agreement) in self.agreement.iter().enumerate() {
+            match agreement {
+                Some(agreement) => ret += &format!("{},{:.4}\n", i, agreement),
+                None => ret += &format!("{},\n", i),
+            }
+        }
+        ret
+    }
+}
+
+impl std::fmt::Display for SurveyStatistics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Groups: {}", self.agreement.len())?;
+        writeln!(f, "Group sizes:")?;
+        for (size, count) in self.group_sizes.iter() {
+            writeln!(f, "  {} members: {} groups", size, count)?;
+        }
+        let most_common: String = self.most_common().into_iter().collect();
+        let least_common: String = self.least_common().into_iter().collect();
+        writeln!(f, "Most common questions: {}", most_common)?;
+        writeln!(f, "Least common questions: {}", least_common)?;
+        match self.mean_agreement() {
+            Some(mean) => writeln!(f, "Mean group agreement: {:.4}", mean)?,
+            None => writeln!(f, "Mean group agreement: n/a")?,
+        }
+        writeln!(f, "Answers per question:")?;
+        write!(f, "{}", self.histogram())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse_input_into_groups;
+
+    fn test_statistics() -> SurveyStatistics {
+        let alphabet = QuestionAlphabet::new("abcd").unwrap();
+        let test_data = include_str!("../test_data.txt");
+        let groups = parse_input_into_groups(test_data, &alphabet).unwrap();
+        SurveyStatistics::new(&groups[..], &alphabet)
+    }
+
+    #[test]
+    fn test_frequencies() {
+        let stats = test_statistics();
+        assert_eq!(stats.answer_frequency, [8, 4, 3, 0]);
+        assert_eq!(stats.group_frequency, [4, 4, 3, 0]);
+        assert_eq!(stats.most_common(), ['a']);
+        assert_eq!(stats.least_common(), ['d']);
+    }
+
+    #[test]
+    fn test_group_sizes() {
+        let stats = test_statistics();
+        let sizes: Vec<(usize, usize)> = stats.group_sizes.into_iter().collect();
+        assert_eq!(sizes, [(1, 2), (2, 1), (3, 1), (4, 1)]);
+    }
+
+    #[test]
+    fn test_agreement() {
+        let stats = test_statistics();
+        assert_eq!(
+            stats.agreement,
+            [Some(1.0), Some(0.0), Some(1.0 / 3.0), Some(1.0), Some(1.0)]
+        );
+        let mean = stats.mean_agreement().unwrap();
+        assert!((mean - (3.0 + 1.0 / 3.0) / 5.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_empty_survey() {
+        let stats = SurveyStatistics::new(&[], &QuestionAlphabet::default());
+        assert_eq!(stats.mean_agreement(), None);
+        assert_eq!(stats.most_common().len(), 26);
+        assert_eq!(stats.histogram().lines().next(), Some("a |  0"));
+    }
+
+    #[test]
+    fn test_histogram() {
+        let stats = test_statistics();
+        let histogram = stats.histogram();
+        let lines: Vec<&str> = histogram.lines().collect();
+        assert_eq!(lines[0], format!("a | {} 8", "#".repeat(50)));
+        assert_eq!(lines[1], format!("b | {} 4", "#".repeat(25)));
+        assert_eq!(lines[3], "d |  0");
+    }
+
+    #[test]
+    fn test_csv() {
+        let stats = test_statistics();
+        assert_eq!(
+            stats.csv(CsvTable::Questions),
+            "question,people,groups\n\"a\",8,4\n\"b\",4,4\n\"c\",3,3\n\"d\",0,0\n"
+        );
+        assert_eq!(
+            stats.csv(CsvTable::Groups),
+            "group,agreement\n0,1.0000\n1,0.0000\n2,0.3333\n3,1.0000\n4,1.0000\n"
+        );
+        assert_eq!("groups".parse(), Ok(CsvTable::Groups));
+        assert!("both".parse::<CsvTable>().is_err());
+    }
+
+    #[test]
+    fn test_csv_quoting() {
+        let alphabet = QuestionAlphabet::new(",\" a").unwrap();
+        let groups = parse_input_into_groups(",\"\n\" a\n", &alphabet).unwrap();
+        let stats = SurveyStatistics::new(&groups[..], &alphabet);
+        assert_eq!(
+            stats.csv(CsvTable::Questions),
+            "question,people,groups\n\",\",1,1\n\"\"\"\",2,1\n\" \",1,1\n\"a\",1,1\n"
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use day7::parser::parse_rules;
+use day7::rule_store::RuleStore;
+use day7::{bag_parser, naive, Bag, BagGraph};
+
+fn parse_input() -> Vec<Bag> {
+    include_str!("../input_data.txt")
+        .lines()
+        .map(bag_parser::parse)
+        .map(Result::unwrap)
+        .collect()
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let input = include_str!("../input_data.txt");
+    let mut group = c.benchmark_group("parse");
+    group.bench_function("rules", |b| b.iter(|| parse_rules(black_box(input))));
+    let parsed = parse_rules(input);
+    group.bench_function("bag graph", |b| b.iter(|| black_box(&parsed).graph()));
+    group.finish();
+}
+
+fn bench_day_a(c: &mut Criterion) {
+    let bags = parse_input();
+    let graph = BagGraph::new(&bags);
+    let mut group = c.benchmark_group("day a");
+    group.bench_function("linear scan", |b| {
+        b.iter(|| naive::calculate_day_a(black_box(&bags), "shiny gold"))
+    });
+    group.bench_function("bag graph", |b| {
+        b.iter(|| day7::calculate_day_a(black_box(&graph), "shiny gold"))
+    });
+    group.bench_function("bag graph including build", |b| {
+        b.iter(|| day7::calculate_day_a(&BagGraph::new(black_box(&bags)), "shiny gold"))
+    });
+    group.finish();
+}
+
+fn bench_day_b(c: &mut Criterion) {
+    let bags = parse_input();
+    let graph = BagGraph::new(&bags);
+    let mut group = c.benchmark_group("day b");
+    group.bench_function("hashmap cache", |b| {
+        b.iter(|| naive::calculate_day_b(black_box(&bags), "shiny gold"))
+    });
+    group.bench_function("bag graph", |b| {
+        b.iter(|| day7::calculate_day_b(black_box(&graph), "shiny gold"))
+    });
+    group.finish();
+}
+
+// Changing one rule deep inside shiny gold, then recalculating part b
+fn bench_what_if(c: &mut Criterion) {
+    let bags = parse_input();
+    let edited = bags
+        .iter()
+        .find(|bag| bag.contains.is_empty())
+        .expect("Some bag should hold nothing")
+        .clone();
+    let mut group = c.benchmark_group("what if");
+    group.bench_function("rebuild bag graph", |b| {
+        b.iter(|| {
+            let mut bags = bags.clone();
+            let position = bags.iter().position(|bag| *bag == edited).unwrap();
+            bags[position] = black_box(edited.clone());
+            day7::calculate_day_b(&BagGraph::new(&bags), "shiny gold")
+        })
+    });
+    let mut store = RuleStore::new(&bags);
+    store.total("shiny gold").unwrap();
+    group.bench_function("rule store", |b| {
+        b.iter(|| {
+            store.insert(black_box(edited.clone()));
+            store.total("shiny gold")
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_parse,
+    bench_day_a,
+    bench_day_b,
+    bench_what_if
+);
+criterion_main!(benches);
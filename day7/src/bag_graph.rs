@@ -0,0 +1,428 @@
+// The bag rules as a graph. Colours are interned to ids so that lookups are
+// just indexing into vectors, and we keep the edges in both directions so that
+// "what holds this bag" is as cheap as "what does this bag hold".
+use crate::count::BagCount;
+use crate::validation::RuleProblem;
+use crate::Bag;
+use std::collections::HashMap;
+
+pub type ColourId = usize;
+
+/// Combines a colour with the values of the bags it directly holds (and how
+/// many of each) into the value for that colour. See `BagGraph::fold_contained`.
+pub type FoldFn<'a, T> = dyn Fn(ColourId, &[(T, usize)]) -> T + 'a;
+
+#[derive(Debug, Default)]
+pub struct BagGraph {
+    colours: Vec<String>,
+    ids: HashMap<String, ColourId>,
+    /// How many rules define the colour. 0 if it was only seen inside another bag.
+    definitions: Vec<usize>,
+    /// colour -> (child colour, quantity) for every bag it directly holds
+    contains: Vec<Vec<(ColourId, usize)>>,
+    /// colour -> (parent colour, quantity) for every bag that directly holds it
+    contained_by: Vec<Vec<(ColourId, usize)>>,
+}
+
+impl BagGraph {
+    pub fn new(bags: &[Bag]) -> BagGraph {
+        let mut graph = BagGraph::default();
+        for bag in bags.iter() {
+            let parent = graph.intern(&bag.colour);
+            graph.definitions[parent] += 1;
+            for child in bag.contains.iter() {
+                let child_id = graph.intern(&child.colour);
+                graph.contains[parent].push((child_id, child.quantity));
+                graph.contained_by[child_id].push((parent, child.quantity));
+            }
+        }
+        graph
+    }
+
+    fn intern(&mut self, colour: &str) -> ColourId {
+        if let Some(id) = self.ids.get(colour) {
+            return *id;
+        }
+        let id = self.colours.len();
+        self.colours.push(String::from(colour));
+        self.ids.insert(String::from(colour), id);
+        self.definitions.push(0);
+        self.contains.push(vec![]);
+        self.contained_by.push(vec![]);
+        id
+    }
+
+    pub fn len(&self) -> usize {
+        self.colours.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.colours.is_empty()
+    }
+
+    pub fn id(&self, colour: &str) -> Option<ColourId> {
+        self.ids.get(colour).copied()
+    }
+
+    pub fn colour(&self, id: ColourId) -> &str {
+        &self.colours[id]
+    }
+
+    pub fn is_defined(&self, id: ColourId) -> bool {
+        self.definitions[id] > 0
+    }
+
+    pub fn definition_count(&self, id: ColourId) -> usize {
+        self.definitions[id]
+    }
+
+    pub fn children(&self, id: ColourId) -> &[(ColourId, usize)] {
+        &self.contains[id]
+    }
+
+    pub fn parents(&self, id: ColourId) -> &[(ColourId, usize)] {
+        &self.contained_by[id]
+    }
+
+    /// Every colour that eventually holds the given colour (not including itself)
+    pub fn ancestors(&self, id: ColourId) -> Vec<ColourId> {
+        self.reachable(id, &self.contained_by)
+    }
+
+    /// Every colour that is eventually held by the given colour (not including itself)
+    pub fn descendants(&self, id: ColourId) -> Vec<ColourId> {
+        self.reachable(id, &self.contains)
+    }
+
+    // Breadth first search along one direction of the edges
+    fn reachable(&self, id: ColourId, edges: &[Vec<(ColourId, usize)>]) -> Vec<ColourId> {
+        let mut seen = vec![false; self.len()];
+        seen[id] = true;
+        let mut found = vec![id];
+        let mut next = 0;
+        while next < found.len() {
+            for (other, _) in edges[found[next]].iter() {
+                if !seen[*other] {
+                    seen[*other] = true;
+                    found.push(*other);
+                }
+            }
+            next += 1;
+        }
+        found.remove(0);
+        found
+    }
+
+    /// Total number of bags held inside one bag of the given colour.
+    /// Fails if the colour (or anything inside it) has no rule, if it
+    /// eventually holds itself, or if the total doesn't fit in a `u128`.
+    pub fn count_contained(&self, id: ColourId) -> Result<u128, RuleProblem> {
+        self.count_contained_as(id)
+    }
+
+    /// `count_contained`, counting with any `BagCount` type. An overflow is
+    /// reported as the rule (parent, child and quantity) where it happened.
+    pub fn count_contained_as<C: BagCount>(&self, id: ColourId) -> Result<C, RuleProblem> {
+        // Each colour's value is the number of bags inside it, or the overflow below it
+        self.fold_contained(id, &|parent, children| {
+            self.count_children(parent, children)
+        })?
+    }
+
+    /// `count_contained` for every colour at once, indexed by `ColourId`,
+    /// sharing the work between colours that hold the same bags. A colour
+    /// that can't be counted gets its own error without affecting the rest.
+    pub fn count_all(&self) -> Vec<Result<u128, RuleProblem>> {
+        self.fold_all(&|parent, children| self.count_children(parent, children))
+            .into_iter()
+            .map(|count| count.and_then(|count| count))
+            .collect()
+    }
+
+    // The number of bags inside `parent`, from the counts of the bags it directly holds
+    fn count_children<C: BagCount>(
+        &self,
+        parent: ColourId,
+        children: &[(Result<C, RuleProblem>, usize)],
+    ) -> Result<C, RuleProblem> {
+        let mut held = C::zero();
+        for ((child, _), (count, quantity)) in self.children(parent).iter().zip(children) {
+            held = count
+                .clone()?
+                .add_count(&C::one())
+                .and_then(|count| count.mul_quantity(*quantity))
+                .and_then(|count| held.add_count(&count))
+                .ok_or_else(|| RuleProblem::Overflow {
+                    colour: String::from(self.colour(parent)),
+                    holds: String::from(self.colour(*child)),
+                    quantity: *quantity,
+                })?;
+        }
+        Ok(held)
+    }
+
+    /// Calculate a value for the given colour from the values of the bags it
+    /// directly holds (paired with how many of each), working up from the
+    /// bags that hold nothing. Each colour is only calculated once.
+    /// Fails if a colour has no rule, or if a bag eventually holds itself.
+    pub fn fold_contained<T: Clone>(
+        &self,
+        id: ColourId,
+        combine: &FoldFn<T>,
+    ) -> Result<T, RuleProblem> {
+        let mut cache = vec![FoldState::Unvisited; self.len()];
+        let mut path = vec![];
+        self.fold_with_cache(id, combine, &mut cache, &mut path)
+    }
+
+    /// `fold_contained` for every colour, indexed by `ColourId`, with one
+    /// cache shared between them so each colour is still only calculated
+    /// once. A colour that fails (and everything holding it) gets the error.
+    pub fn fold_all<T: Clone>(&self, combine: &FoldFn<T>) -> Vec<Result<T, RuleProblem>> {
+        let mut cache = vec![FoldState::Unvisited; self.len()];
+        (0..self.len())
+            .map(|id| self.fold_with_cache(id, combine, &mut cache, &mut vec![]))
+            .collect()
+    }
+
+    // `path` is the chain of bags we are currently inside of, used to report cycles.
+    fn fold_with_cache<T: Clone>(
+        &self,
+        id: ColourId,
+        combine: &FoldFn<T>,
+        cache: &mut Vec<FoldState<T>>,
+        path: &mut Vec<ColourId>,
+    ) -> Result<T, RuleProblem> {
+        match &cache[id] {
+            FoldState::Done(value) => return Ok(value.clone()),
+            FoldState::Failed(problem) => return Err(problem.clone()),
+            FoldState::InProgress => {
+                let start = path.iter().position(|bag| *bag == id).unwrap();
+                let mut cycle = path[start..].to_vec();
+                cycle.push(id);
+                return Err(RuleProblem::Cycle(self.colours_for(&cycle)));
+            }
+            FoldState::Unvisited => (),
+        }
+        if !self.is_defined(id) {
+            return Err(RuleProblem::UndefinedColour {
+                colour: String::from(self.colour(id)),
+                used_by: self.colours_for(&path[path.len().saturating_sub(1)..]),
+            });
+        }
+        cache[id] = FoldState::InProgress;
+        path.push(id);
+        let mut children = Vec::<(T, usize)>::new();
+        for (child, quantity) in self.children(id).iter() {
+            match self.fold_with_cache(*child, combine, cache, path) {
+                Ok(value) => children.push((value, *quantity)),
+                Err(problem) => {
+                    // Remembered so a later fold over the same cache doesn't
+                    // find this colour still in progress and call it a cycle
+                    cache[id] = FoldState::Failed(problem.clone());
+                    path.pop();
+                    return Err(problem);
+                }
+            }
+        }
+        path.pop();
+        let value = combine(id, &children);
+        cache[id] = FoldState::Done(value.clone());
+        Ok(value)
+    }
+
+    pub fn colours_for(&self, ids: &[ColourId]) -> Vec<String> {
+        ids.iter()
+            .map(|id| String::from(self.colour(*id)))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+enum FoldState<T> {
+    Unvisited,
+    InProgress,
+    Done(T),
+    Failed(RuleProblem),
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bag_parser;
+    use crate::validation::RuleProblem;
+    use crate::{Bag, BagGraph};
+
+    fn parse_graph(lines: &str) -> BagGraph {
+        let bags: Vec<Bag> = lines
+            .lines()
+            .map(bag_parser::parse)
+            .map(Result::unwrap)
+            .collect();
+        BagGraph::new(&bags)
+    }
+
+    #[test]
+    fn test_build_graph() {
+        let graph = parse_graph(include_str!("../test_data_a.txt"));
+        assert_eq!(graph.len(), 9);
+        let light_red = graph.id("light red").unwrap();
+        let bright_white = graph.id("bright white").unwrap();
+        let muted_yellow = graph.id("muted yellow").unwrap();
+        assert_eq!(graph.colour(light_red), "light red");
+        assert_eq!(
+            graph.children(light_red),
+            [(bright_white, 1), (muted_yellow, 2)]
+        );
+        let shiny_gold = graph.id("shiny gold").unwrap();
+        assert_eq!(
+            graph.parents(shiny_gold),
+            [(bright_white, 1), (muted_yellow, 2)]
+        );
+        assert_eq!(graph.id("tartan"), None);
+    }
+
+    #[test]
+    fn test_undefined_colours() {
+        let graph = parse_graph("red bags contain 2 blue bags.");
+        assert!(graph.is_defined(graph.id("red").unwrap()));
+        assert!(!graph.is_defined(graph.id("blue").unwrap()));
+    }
+
+    #[test]
+    fn test_ancestors() {
+        let graph = parse_graph(include_str!("../test_data_a.txt"));
+        let mut ancestors: Vec<&str> = graph
+            .ancestors(graph.id("shiny gold").unwrap())
+            .into_iter()
+            .map(|id| graph.colour(id))
+            .collect();
+        ancestors.sort_unstable();
+        assert_eq!(
+            ancestors,
+            ["bright white", "dark orange", "light red", "muted yellow"]
+        );
+    }
+
+    #[test]
+    fn test_count_contained() {
+        let graph = parse_graph(include_str!("../test_data_a.txt"));
+        assert_eq!(
+            graph.count_contained(graph.id("shiny gold").unwrap()),
+            Ok(32)
+        );
+        assert_eq!(
+            graph.count_contained(graph.id("faded blue").unwrap()),
+            Ok(0)
+        );
+    }
+
+    #[test]
+    fn test_count_contained_cycle() {
+        let graph = parse_graph(
+            "red bags contain 1 blue bag.\n\
+             blue bags contain 2 green bags.\n\
+             green bags contain 1 red bag, 1 white bag.\n\
+             white bags contain no other bags.",
+        );
+        assert_eq!(
+            graph.count_contained(graph.id("red").unwrap()),
+            Err(RuleProblem::Cycle(vec![
+                String::from("red"),
+                String::from("blue"),
+                String::from("green"),
+                String::from("red")
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_count_contained_undefined() {
+        let graph = parse_graph("red bags contain 1 blue bag.");
+        assert_eq!(
+            graph.count_contained(graph.id("red").unwrap()),
+            Err(RuleProblem::UndefinedColour {
+                colour: String::from("blue"),
+                used_by: vec![String::from("red")]
+            })
+        );
+    }
+
+    #[test]
+    fn test_count_all() {
+        let graph = parse_graph(
+            "red bags contain 1 blue bag, 2 white bags.\n\
+             blue bags contain 1 red bag.\n\
+             green bags contain 3 white bags.\n\
+             gold bags contain 1 grey bag.\n\
+             white bags contain no other bags.",
+        );
+        let counts = graph.count_all();
+        let count = |colour| counts[graph.id(colour).unwrap()].clone();
+        assert_eq!(count("green"), Ok(3));
+        assert_eq!(count("white"), Ok(0));
+        assert!(matches!(count("red"), Err(RuleProblem::Cycle(_))));
+        assert!(matches!(count("blue"), Err(RuleProblem::Cycle(_))));
+        assert!(matches!(
+            count("gold"),
+            Err(RuleProblem::UndefinedColour { .. })
+        ));
+        assert!(matches!(
+            count("grey"),
+            Err(RuleProblem::UndefinedColour { .. })
+        ));
+    }
+
+    // 14 layers of bags, each holding 1000 of the next, so the outer bag holds
+    // 1000 + 1000^2 + ... + 1000^13 bags
+    fn deep_graph() -> BagGraph {
+        let layers: Vec<String> = ('a'..='n').map(|c| format!("layer {}", c)).collect();
+        let mut rules: Vec<String> = layers
+            .windows(2)
+            .map(|pair| format!("{} bags contain 1000 {} bags.", pair[0], pair[1]))
+            .collect();
+        rules.push(format!("{} bags contain no other bags.", layers[13]));
+        parse_graph(&rules.join("\n"))
+    }
+
+    #[test]
+    fn test_count_contained_overflow() {
+        let graph = deep_graph();
+        let outer = graph.id("layer a").unwrap();
+        assert_eq!(
+            graph.count_contained(outer),
+            Err(RuleProblem::Overflow {
+                colour: String::from("layer a"),
+                holds: String::from("layer b"),
+                quantity: 1000
+            })
+        );
+        assert_eq!(
+            graph.count_contained_as::<u64>(outer),
+            Err(RuleProblem::Overflow {
+                colour: String::from("layer g"),
+                holds: String::from("layer h"),
+                quantity: 1000
+            })
+        );
+        assert_eq!(
+            graph.count_contained_as::<u64>(graph.id("layer h").unwrap()),
+            Ok(1_001_001_001_001_001_000)
+        );
+        let counts = graph.count_all();
+        assert!(counts[outer].is_err());
+        assert_eq!(counts[graph.id("layer m").unwrap()], Ok(1000));
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn test_count_contained_bigint() {
+        let graph = deep_graph();
+        let count = graph
+            .count_contained_as::<num_bigint::BigUint>(graph.id("layer a").unwrap())
+            .unwrap();
+        assert_eq!(
+            count.to_string(),
+            "1001001001001001001001001001001001001000"
+        );
+    }
+}
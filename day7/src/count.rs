@@ -0,0 +1,86 @@
+// The number types part b can count bags with. Totals grow with the product
+// of the quantities down every chain of bags, so deep or wide rules overflow
+// a `usize` quickly. Every step is checked so an overflow is reported (with
+// the rule that caused it) rather than wrapping or panicking.
+use std::fmt::{Debug, Display};
+
+/// A number of bags. Implemented for the unsigned integers, and for
+/// `num_bigint::BigUint` with the `bigint` feature, which never overflows.
+pub trait BagCount: Clone + Debug + Display + PartialEq {
+    fn zero() -> Self;
+    fn one() -> Self;
+    /// `self + other`, or `None` if it doesn't fit
+    fn add_count(&self, other: &Self) -> Option<Self>;
+    /// `self * quantity`, or `None` if it doesn't fit
+    fn mul_quantity(&self, quantity: usize) -> Option<Self>;
+}
+
+macro_rules! impl_bag_count {
+    ($($t:ty),*) => {$(
+        impl BagCount for $t {
+            fn zero() -> Self {
+                0
+            }
+
+            fn one() -> Self {
+                1
+            }
+
+            fn add_count(&self, other: &Self) -> Option<Self> {
+                self.checked_add(*other)
+            }
+
+            fn mul_quantity(&self, quantity: usize) -> Option<Self> {
+                use std::convert::TryFrom;
+                <$t>::try_from(quantity)
+                    .ok()
+                    .and_then(|quantity| self.checked_mul(quantity))
+            }
+        }
+    )*};
+}
+
+impl_bag_count!(u32, u64, u128, usize);
+
+#[cfg(feature = "bigint")]
+impl BagCount for num_bigint::BigUint {
+    fn zero() -> Self {
+        num_bigint::BigUint::from(0u32)
+    }
+
+    fn one() -> Self {
+        num_bigint::BigUint::from(1u32)
+    }
+
+    fn add_count(&self, other: &Self) -> Option<Self> {
+        Some(self + other)
+    }
+
+    fn mul_quantity(&self, quantity: usize) -> Option<Self> {
+        Some(self * quantity)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_checked() {
+        assert_eq!(5u32.mul_quantity(3), Some(15));
+        assert_eq!(u32::MAX.add_count(&1), None);
+        assert_eq!(2u32.mul_quantity(usize::MAX), None);
+        assert_eq!(u128::MAX.mul_quantity(2), None);
+        assert_eq!(u128::one().add_count(&u128::zero()), Some(1));
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn test_bigint() {
+        let big = num_bigint::BigUint::from(u128::MAX);
+        assert_eq!(
+            big.mul_quantity(2).unwrap().to_string(),
+            "680564733841876926926749214863536422910"
+        );
+    }
+}
@@ -0,0 +1,283 @@
+// Write the rule graph out in formats other tools can draw, so large rule sets
+// can be reviewed visually.
+use crate::bag_graph::{BagGraph, ColourId};
+use crate::validation::RuleProblem;
+
+/// Which part of the graph to export
+#[derive(Debug, Clone, Copy)]
+pub enum Subgraph<'a> {
+    All,
+    /// The colour and every bag that is eventually inside it
+    From(&'a str),
+    /// The colour and every bag that eventually holds it
+    To(&'a str),
+    /// Every bag on a chain from the first colour down to the second, i.e.
+    /// the overlap of `From(first)` and `To(second)`
+    Between(&'a str, &'a str),
+}
+
+// Which colours are part of the subgraph, indexed by colour id
+fn selection(graph: &BagGraph, subgraph: Subgraph) -> Result<Vec<bool>, RuleProblem> {
+    let (id, others) = match subgraph {
+        Subgraph::All => return Ok(vec![true; graph.len()]),
+        Subgraph::Between(from, to) => {
+            let below = selection(graph, Subgraph::From(from))?;
+            let above = selection(graph, Subgraph::To(to))?;
+            return Ok(below.iter().zip(above).map(|(a, b)| *a && b).collect());
+        }
+        Subgraph::From(colour) | Subgraph::To(colour) => {
+            let id = graph
+                .id(colour)
+                .ok_or_else(|| RuleProblem::UndefinedColour {
+                    colour: String::from(colour),
+                    used_by: vec![],
+                })?;
+            match subgraph {
+                Subgraph::From(_) => (id, graph.descendants(id)),
+                _ => (id, graph.ancestors(id)),
+            }
+        }
+    };
+    let mut selected = vec![false; graph.len()];
+    selected[id] = true;
+    for other in others {
+        selected[other] = true;
+    }
+    Ok(selected)
+}
+
+// Every (parent, child, quantity) edge between two selected colours
+fn selected_edges(graph: &BagGraph, selected: &[bool]) -> Vec<(ColourId, ColourId, usize)> {
+    let mut edges = vec![];
+    for parent in (0..graph.len()).filter(|id| selected[*id]) {
+        for (child, quantity) in graph.children(parent).iter() {
+            if selected[*child] {
+                edges.push((parent, *child, *quantity));
+            }
+        }
+    }
+    edges
+}
+
+// Escape a colour for use inside a double quoted string in DOT or JSON
+fn quote(colour: &str) -> String {
+    format!("\"{}\"", colour.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Graphviz DOT, with each edge labelled by the quantity,
+/// e.g. `"light red" -> "bright white" [label="1"];`
+pub fn to_dot(graph: &BagGraph, subgraph: Subgraph) -> Result<String, RuleProblem> {
+    let selected = selection(graph, subgraph)?;
+    let mut ret = String::from("digraph bags {\n");
+    for id in (0..graph.len()).filter(|id| selected[*id]) {
+        ret += &format!("    {};\n", quote(graph.colour(id)));
+    }
+    for (parent, child, quantity) in selected_edges(graph, &selected) {
+        ret += &format!(
+            "    {} -> {} [label=\"{}\"];\n",
+            quote(graph.colour(parent)),
+            quote(graph.colour(child)),
+            quantity
+        );
+    }
+    ret += "}\n";
+    Ok(ret)
+}
+
+/// A Mermaid flowchart. Colours have spaces so each node gets an id
+/// (`bag` + colour id) with the colour as its label.
+pub fn to_mermaid(graph: &BagGraph, subgraph: Subgraph) -> Result<String, RuleProblem> {
+    let selected = selection(graph, subgraph)?;
+    let mut ret = String::from("flowchart TD\n");
+    for id in (0..graph.len()).filter(|id| selected[*id]) {
+        ret += &format!(
+            "    bag{}[\"{}\"]\n",
+            id,
+            graph.colour(id).replace('"', "#quot;")
+        );
+    }
+    for (parent, child, quantity) in selected_edges(graph, &selected) {
+        ret += &format!("    bag{} -->|{}| bag{}\n", parent, quantity, child);
+    }
+    Ok(ret)
+}
+
+/// JSON adjacency, mapping each colour to the colours it directly holds and
+/// how many of each, e.g. `{"bright white": {"shiny gold": 1}}`. A rule that
+/// lists the same colour more than once gets one key with the quantities summed,
+/// failing with `RuleProblem::Overflow` if the sum doesn't fit in a `usize`.
+pub fn to_json(graph: &BagGraph, subgraph: Subgraph) -> Result<String, RuleProblem> {
+    let selected = selection(graph, subgraph)?;
+    let mut entries = Vec::<String>::new();
+    for parent in (0..graph.len()).filter(|id| selected[*id]) {
+        let mut quantities = Vec::<(ColourId, usize)>::new();
+        for (child, quantity) in graph.children(parent).iter() {
+            if !selected[*child] {
+                continue;
+            }
+            match quantities.iter_mut().find(|(seen, _)| seen == child) {
+                Some((_, total)) => {
+                    *total = total
+                        .checked_add(*quantity)
+                        .ok_or_else(|| RuleProblem::Overflow {
+                            colour: String::from(graph.colour(parent)),
+                            holds: String::from(graph.colour(*child)),
+                            quantity: *quantity,
+                        })?
+                }
+                None => quantities.push((*child, *quantity)),
+            }
+        }
+        let children: Vec<String> = quantities
+            .iter()
+            .map(|(child, quantity)| format!("{}: {}", quote(graph.colour(*child)), quantity))
+            .collect();
+        entries.push(format!(
+            "  {}: {{{}}}",
+            quote(graph.colour(parent)),
+            children.join(", ")
+        ));
+    }
+    Ok(format!("{{\n{}\n}}\n", entries.join(",\n")))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{bag_parser, Bag};
+
+    fn parse_graph(lines: &str) -> BagGraph {
+        let bags: Vec<Bag> = lines
+            .lines()
+            .map(bag_parser::parse)
+            .map(Result::unwrap)
+            .collect();
+        BagGraph::new(&bags)
+    }
+
+    fn small_graph() -> BagGraph {
+        parse_graph(
+            "light red bags contain 1 bright white bag, 2 muted yellow bags.\n\
+             bright white bags contain 1 shiny gold bag.\n\
+             muted yellow bags contain no other bags.\n\
+             shiny gold bags contain no other bags.",
+        )
+    }
+
+    #[test]
+    fn test_dot() {
+        assert_eq!(
+            to_dot(&small_graph(), Subgraph::All).unwrap(),
+            "digraph bags {\n\
+             \x20   \"light red\";\n\
+             \x20   \"bright white\";\n\
+             \x20   \"muted yellow\";\n\
+             \x20   \"shiny gold\";\n\
+             \x20   \"light red\" -> \"bright white\" [label=\"1\"];\n\
+             \x20   \"light red\" -> \"muted yellow\" [label=\"2\"];\n\
+             \x20   \"bright white\" -> \"shiny gold\" [label=\"1\"];\n\
+             }\n"
+        );
+    }
+
+    #[test]
+    fn test_mermaid() {
+        assert_eq!(
+            to_mermaid(&small_graph(), Subgraph::From("bright white")).unwrap(),
+            "flowchart TD\n\
+             \x20   bag1[\"bright white\"]\n\
+             \x20   bag3[\"shiny gold\"]\n\
+             \x20   bag1 -->|1| bag3\n"
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        assert_eq!(
+            to_json(&small_graph(), Subgraph::All).unwrap(),
+            "{\n\
+             \x20 \"light red\": {\"bright white\": 1, \"muted yellow\": 2},\n\
+             \x20 \"bright white\": {\"shiny gold\": 1},\n\
+             \x20 \"muted yellow\": {},\n\
+             \x20 \"shiny gold\": {}\n\
+             }\n"
+        );
+    }
+
+    #[test]
+    fn test_subgraph_to() {
+        assert_eq!(
+            to_json(&small_graph(), Subgraph::To("shiny gold")).unwrap(),
+            "{\n\
+             \x20 \"light red\": {\"bright white\": 1},\n\
+             \x20 \"bright white\": {\"shiny gold\": 1},\n\
+             \x20 \"shiny gold\": {}\n\
+             }\n"
+        );
+    }
+
+    #[test]
+    fn test_json_repeated_child() {
+        let graph = parse_graph(
+            "red bags contain 1 blue bag, 2 green bags, 3 blue bags.\n\
+             blue bags contain no other bags.\n\
+             green bags contain no other bags.",
+        );
+        assert_eq!(
+            to_json(&graph, Subgraph::All).unwrap(),
+            "{\n\
+             \x20 \"red\": {\"blue\": 4, \"green\": 2},\n\
+             \x20 \"blue\": {},\n\
+             \x20 \"green\": {}\n\
+             }\n"
+        );
+    }
+
+    #[test]
+    fn test_json_repeated_child_overflow() {
+        let graph = parse_graph(
+            "red bags contain 18000000000000000000 blue bags, 18000000000000000000 blue bags.\n\
+             blue bags contain no other bags.",
+        );
+        assert_eq!(
+            to_json(&graph, Subgraph::All),
+            Err(RuleProblem::Overflow {
+                colour: String::from("red"),
+                holds: String::from("blue"),
+                quantity: 18000000000000000000
+            })
+        );
+    }
+
+    #[test]
+    fn test_subgraph_between() {
+        assert_eq!(
+            to_json(&small_graph(), Subgraph::Between("light red", "shiny gold")).unwrap(),
+            "{\n\
+             \x20 \"light red\": {\"bright white\": 1},\n\
+             \x20 \"bright white\": {\"shiny gold\": 1},\n\
+             \x20 \"shiny gold\": {}\n\
+             }\n"
+        );
+        assert_eq!(
+            to_json(
+                &small_graph(),
+                Subgraph::Between("muted yellow", "shiny gold")
+            )
+            .unwrap(),
+            "{\n\n}\n"
+        );
+        assert!(to_dot(&small_graph(), Subgraph::Between("light red", "tartan")).is_err());
+    }
+
+    #[test]
+    fn test_subgraph_unknown_colour() {
+        assert!(to_dot(&small_graph(), Subgraph::From("tartan")).is_err());
+    }
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote("plain"), "\"plain\"");
+        assert_eq!(quote("say \"hi\"\\"), "\"say \\\"hi\\\"\\\\\"");
+    }
+}
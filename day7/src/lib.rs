@@ -0,0 +1,253 @@
+extern crate peg;
+
+pub mod bag_graph;
+pub mod count;
+pub mod export;
+pub mod naive;
+pub mod parser;
+pub mod queries;
+pub mod rule_store;
+pub mod validation;
+
+pub use bag_graph::BagGraph;
+pub use parser::bag_parser;
+use validation::RuleProblem;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct BagQuantity {
+    pub colour: String,
+    pub quantity: usize,
+}
+#[derive(Debug, PartialEq, Clone)]
+pub struct Bag {
+    pub colour: String,
+    pub contains: Vec<BagQuantity>,
+}
+
+// Writes e.g. "1 bright white bag" or "2 muted yellow bags"
+impl std::fmt::Display for BagQuantity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let plural = if self.quantity == 1 { "" } else { "s" };
+        write!(f, "{} {} bag{}", self.quantity, self.colour, plural)
+    }
+}
+
+// Writes the rule back out in the same sentence form `bag_parser::parse` reads,
+// so parsing the output gives back an identical `Bag`.
+impl std::fmt::Display for Bag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} bags contain ", self.colour)?;
+        if self.contains.is_empty() {
+            return write!(f, "no other bags.");
+        }
+        for (i, bag_quantity) in self.contains.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", bag_quantity)?;
+        }
+        write!(f, ".")
+    }
+}
+
+/// Calculate how many different bag types can eventually point to the given
+/// bag to calculate. e.g. if a red bag holds a shiny gold bag, and a blue bag
+/// holds a red bag, then both red bag and blue bag eventually point to a
+/// shing gold bag, so the answer for shiny gold bag is 2.
+pub fn calculate_day_a(graph: &BagGraph, bag_to_calculate: &str) -> usize {
+    match graph.id(bag_to_calculate) {
+        Some(id) => graph.ancestors(id).len(),
+        None => 0,
+    }
+}
+
+// Calculate how many bags total a given bag can hold.
+// If bag green holds 5 red, and bag red holds 2 blue, then 5 red bags has 10 blue
+// bags, as well as the 5 red bags, so 1 green bag has 15 bags
+//
+// Fails rather than panicking if a bag eventually holds itself, if any of
+// the bags involved (including the one asked about) have no rule, or if the
+// total is too big for a u128.
+pub fn calculate_day_b(graph: &BagGraph, bag_to_calculate: &str) -> Result<u128, RuleProblem> {
+    match graph.id(bag_to_calculate) {
+        Some(id) => graph.count_contained(id),
+        None => Err(RuleProblem::UndefinedColour {
+            colour: String::from(bag_to_calculate),
+            used_by: vec![],
+        }),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bag_parser;
+    use crate::calculate_day_a;
+    use crate::calculate_day_b;
+    use crate::Bag;
+    use crate::BagGraph;
+    use crate::BagQuantity;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_parse_bag() {
+        let bag = bag_parser::parse("green bags contain 2 red bags, 4 blue bags.");
+        if let Ok(bag) = bag {
+            assert_eq!(bag.colour, "green");
+            assert_eq!(bag.contains.len(), 2);
+            assert_eq!(bag.contains[0].colour, "red");
+            assert_eq!(bag.contains[0].quantity, 2);
+            assert_eq!(bag.contains[1].colour, "blue");
+            assert_eq!(bag.contains[1].quantity, 4);
+        } else {
+            // consider testing with `cargo test --features peg/trace`
+            panic!("bag had an error, {:?}", bag);
+        }
+    }
+
+    #[test]
+    fn test_parse_bag_larger_colours() {
+        let bag = bag_parser::parse("dark green bags contain 2 dull red bags, 1 blueish grey bag.");
+        if let Ok(bag) = bag {
+            assert_eq!(bag.colour, "dark green");
+            assert_eq!(bag.contains.len(), 2);
+            assert_eq!(bag.contains[0].colour, "dull red");
+            assert_eq!(bag.contains[0].quantity, 2);
+            assert_eq!(bag.contains[1].colour, "blueish grey");
+            assert_eq!(bag.contains[1].quantity, 1);
+        } else {
+            panic!("bag had an error, {:?}", bag);
+        }
+    }
+
+    #[test]
+    fn test_parse_bag_no_bags_inside() {
+        let bag = bag_parser::parse("faded blue bags contain no other bags.");
+        if let Ok(bag) = bag {
+            assert_eq!(bag.colour, "faded blue");
+            assert_eq!(bag.contains.len(), 0);
+        } else {
+            panic!("bag had an error, {:?}", bag);
+        }
+    }
+
+    #[test]
+    fn test_day_a_problem() {
+        let lines: &str = include_str!("../test_data_a.txt");
+        let bags: Vec<Bag> = lines
+            .lines()
+            .map(bag_parser::parse)
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(calculate_day_a(&BagGraph::new(&bags), "shiny gold"), 4);
+    }
+
+    #[test]
+    fn test_day_b_problem() {
+        let lines: &str = include_str!("../test_data_b.txt");
+        let bags: Vec<Bag> = lines
+            .lines()
+            .map(bag_parser::parse)
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(
+            calculate_day_b(&BagGraph::new(&bags), "shiny gold"),
+            Ok(126)
+        )
+    }
+
+    #[test]
+    fn test_unknown_colour() {
+        let lines: &str = include_str!("../test_data_a.txt");
+        let bags: Vec<Bag> = lines
+            .lines()
+            .map(bag_parser::parse)
+            .map(Result::unwrap)
+            .collect();
+        let graph = BagGraph::new(&bags);
+        assert_eq!(calculate_day_a(&graph, "tartan"), 0);
+        assert!(calculate_day_b(&graph, "tartan").is_err());
+    }
+
+    #[test]
+    fn test_print_bag() {
+        for line in [
+            "light red bags contain 1 bright white bag, 2 muted yellow bags.",
+            "bright white bags contain 1 shiny gold bag.",
+            "faded blue bags contain no other bags.",
+        ] {
+            assert_eq!(bag_parser::parse(line).unwrap().to_string(), line);
+        }
+    }
+
+    #[test]
+    fn test_print_canonicalises() {
+        let bag = bag_parser::parse("red bags contain 1 blue bags, 2 green bag").unwrap();
+        assert_eq!(
+            bag.to_string(),
+            "red bags contain 1 blue bag, 2 green bags."
+        );
+    }
+
+    #[test]
+    fn test_print_input_data() {
+        let lines: &str = include_str!("../input_data.txt");
+        for line in lines.lines() {
+            assert_eq!(bag_parser::parse(line).unwrap().to_string(), line);
+        }
+    }
+
+    // Colours are one or more lowercase words. Words can't be "bag" or "bags",
+    // as that is how the parser knows the colour has finished.
+    fn colour_strategy() -> impl Strategy<Value = String> {
+        prop::collection::vec("[a-z]{1,8}", 1..4)
+            .prop_filter("words can't be bag or bags", |words| {
+                words.iter().all(|word| word != "bag" && word != "bags")
+            })
+            .prop_map(|words| words.join(" "))
+    }
+
+    fn bag_strategy() -> impl Strategy<Value = Bag> {
+        (
+            colour_strategy(),
+            prop::collection::vec((colour_strategy(), 0..1000usize), 0..6),
+        )
+            .prop_map(|(colour, contains)| Bag {
+                colour,
+                contains: contains
+                    .into_iter()
+                    .map(|(colour, quantity)| BagQuantity { colour, quantity })
+                    .collect(),
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn test_parse_print_round_trip(bag in bag_strategy()) {
+            prop_assert_eq!(bag_parser::parse(&bag.to_string()), Ok(bag));
+        }
+
+        #[test]
+        fn test_parse_print_round_trip_rule_set(bags in prop::collection::vec(bag_strategy(), 0..20)) {
+            let printed: Vec<String> = bags.iter().map(Bag::to_string).collect();
+            let reparsed: Vec<Bag> = printed
+                .join("\n")
+                .lines()
+                .map(bag_parser::parse)
+                .map(Result::unwrap)
+                .collect();
+            prop_assert_eq!(reparsed, bags);
+        }
+    }
+
+    #[test]
+    fn test_generated() {
+        for (seed, size) in [(1, 1), (2, 10), (3, 500), (4, 5000)] {
+            let generated = generators::day7::generate(seed, size);
+            let parsed = crate::parser::parse_rules(&generated.input);
+            assert!(parsed.diagnostics.is_empty());
+            let graph = parsed.graph();
+            assert_eq!(calculate_day_a(&graph, "shiny gold"), generated.part_a);
+            assert_eq!(calculate_day_b(&graph, "shiny gold"), Ok(generated.part_b));
+        }
+    }
+}
@@ -1,196 +1,167 @@
-extern crate peg;
-#[derive(Debug)]
-pub struct BagQuantity {
-    colour: String,
-    quantity: usize,
-}
-#[derive(Debug)]
-pub struct Bag {
-    colour: String,
-    contains: Vec<BagQuantity>,
-}
-fn find_parents_for_colour<'a>(bags: &'a [Bag], bag_to_calculate: &str) -> Vec<&'a str> {
-    let mut ret: Vec<&'a str> = vec![];
-    for bag in bags.iter() {
-        for child_bag in bag.contains.iter() {
-            if child_bag.colour == bag_to_calculate {
-                ret.push(&bag.colour[..]);
-            }
-        }
-    }
-    ret
-}
+use day7::export::{to_dot, to_json, to_mermaid, Subgraph};
+use day7::parser::parse_rule;
+use day7::parser::parse_rules;
+use day7::queries::{
+    bags_holding_at_most, colours_holding_exactly, containment_paths, explain_day_b, leaf_bags,
+    nesting_depth, smallest_container,
+};
+use day7::rule_store::RuleStore;
+use day7::validation::{validate, RuleProblem};
+use day7::{calculate_day_a, calculate_day_b};
+use harness::{AnswerCheck, Mode, Timings};
 
-/// Calculate how many different bag types can eventually point to the given
-/// bag to calculate. e.g. if a red bag holds a shiny gold bag, and a blue bag
-/// holds a red bag, then both red bag and blue bag eventually point to a
-/// shing gold bag, so the answer for shiny gold bag is 2.
-fn calculate_day_a(bags: &[Bag], bag_to_calculate: &str) -> usize {
-    let mut count = 0;
-    let mut bags_found: Vec<&str> = vec![bag_to_calculate];
-
-    while count < bags_found.len() {
-        let bag = bags_found[count];
-        let new_bags = find_parents_for_colour(bags, bag);
-        for new_bag in new_bags.iter() {
-            if !bags_found.contains(new_bag) {
-                bags_found.push(new_bag);
+fn main() {
+    // Usage: day7 [--paths] [--explain] [--what-if RULE]... [--at-most N] [--exactly N]
+    //             [--time | --bench] [--record]
+    //        day7 (--dot | --mermaid | --json) [--from COLOUR] [--to COLOUR]
+    let mut show_paths = false;
+    let mut show_explain = false;
+    let mut export_format = None;
+    let mut export_from = None;
+    let mut export_to = None;
+    let mut what_ifs = vec![];
+    let mut at_most = None;
+    let mut exactly = None;
+    let mut mode = Mode::Off;
+    let mut record = false;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "--paths" => show_paths = true,
+            "--explain" => show_explain = true,
+            "--dot" | "--mermaid" | "--json" => export_format = Some(arg),
+            "--from" => export_from = Some(args.next().expect("--from needs a colour")),
+            "--to" => export_to = Some(args.next().expect("--to needs a colour")),
+            "--what-if" => what_ifs.push(args.next().expect("--what-if needs a rule")),
+            "--at-most" => {
+                at_most = args
+                    .next()
+                    .map(|n| n.parse::<u128>().expect("--at-most needs a number"))
             }
-        }
-        count += 1;
-    }
-
-    count - 1 // don't include the starting bag
-}
-
-struct BagWithCachedResult<'a> {
-    bag: &'a Bag,
-    result: Option<usize>,
-}
-
-fn get_count_for_bag_colour(
-    ret_dict: &mut std::collections::HashMap<&str, BagWithCachedResult>,
-    bag_to_find: &str,
-) -> usize {
-    match (ret_dict[bag_to_find].result, ret_dict[bag_to_find].bag) {
-        (Some(ret), _) => ret,
-        (None, bag) => {
-            let mut count = 1;
-            for bag_container in bag.contains.iter() {
-                count += bag_container.quantity
-                    * get_count_for_bag_colour(ret_dict, &bag_container.colour[..]);
+            "--exactly" => {
+                exactly = args
+                    .next()
+                    .map(|n| n.parse::<u128>().expect("--exactly needs a number"))
             }
-            ret_dict.get_mut(bag_to_find).unwrap().result = Some(count);
-            count
+            "--time" | "--bench" => mode = Mode::from_arg(&arg).unwrap(),
+            "--record" => record = true,
+            _ => panic!("Unknown argument {}", arg),
         }
     }
-}
-// Calculate how many bags total a given bag can hold.
-// If bag green holds 5 red, and bag red holds 2 blue, then 5 red bags has 10 blue
-// bags, as well as the 5 red bags, so 1 green bag has 15 bags
-fn calculate_day_b(bags: &[Bag], bag_to_calculate: &str) -> usize {
-    let mut ret_dict = std::collections::HashMap::<&str, BagWithCachedResult>::new();
-    for bag in bags.iter() {
-        ret_dict.insert(&bag.colour[..], BagWithCachedResult { bag, result: None });
+    let lines: &str = include_str!("../input_data.txt");
+    let mut timings = Timings::new(mode);
+    let mut answers = AnswerCheck::new(
+        env!("CARGO_MANIFEST_DIR"),
+        env!("CARGO_PKG_NAME"),
+        lines,
+        record,
+    );
+    let parsed = timings.time("parse", || parse_rules(lines));
+    for err in parsed.diagnostics.iter() {
+        println!("Skipping invalid rule, {}", err);
     }
-    get_count_for_bag_colour(&mut ret_dict, bag_to_calculate) - 1 // don't include the given bag in the result
-}
-
-// Parse the each input line into a Bag object.
-// Bags have a colour, and can contain other bags of given colours.
-//
-// The line to parse follows this format:
-// green bags contain 4 red bags.
-// red bags contain 2 blue bags, 1 yellow bag, 5 purple yellow bags.
-// blue bags contain no other bags.
-// yellow bags contain 1 shiny blue bag.
-// purple yellow bags contain no other bags.
-// shiny blue bags contain no other bags.
-peg::parser! { grammar bag_parser() for str {
-    rule number() -> usize
-        = n:$(['0'..='9']+) {n.parse().unwrap()}
-
-    rule bag_quantity() -> BagQuantity
-        = count:number() " " colour:colour() " bag" "s"? "."? {
-            BagQuantity{ colour, quantity: count }
+    let graph = timings.time("build graph", || parsed.graph());
+    if let Some(export_format) = export_format {
+        let subgraph = match (&export_from, &export_to) {
+            (Some(from), Some(to)) => Subgraph::Between(from, to),
+            (Some(colour), None) => Subgraph::From(colour),
+            (None, Some(colour)) => Subgraph::To(colour),
+            (None, None) => Subgraph::All,
+        };
+        let exported = match &export_format[..] {
+            "--dot" => to_dot(&graph, subgraph),
+            "--mermaid" => to_mermaid(&graph, subgraph),
+            _ => to_json(&graph, subgraph),
+        };
+        match exported {
+            Ok(exported) => print!("{}", exported),
+            Err(problem) => println!("Could not export: {}", problem),
         }
-
-    rule inner_bags() -> Vec<BagQuantity>
-        = b:bag_quantity() ** ", " { b }
-
-    rule no_other_bags() -> Vec<BagQuantity>
-        = "no other bags." { vec![] }
-
-    rule not_bag() -> ()
-        = !(" bag")
-
-    rule colour() -> String
-        = c:$((not_bag()[^'0'..='9'])+) {c.to_string()}
-
-    pub rule parse() -> Bag
-        = colour:colour() (" bags contain ") contains:(no_other_bags() / inner_bags()) {
-            Bag{ colour, contains }
-        }
-}}
-
-fn main() {
-    let lines: &str = include_str!("../input_data.txt");
-    let bags: Vec<Bag> = lines
-        .lines()
-        .map(bag_parser::parse)
-        .map(Result::unwrap)
-        .collect();
-    println!("Day a result: {}", calculate_day_a(&bags, "shiny gold"));
-    println!("Day b result: {}", calculate_day_b(&bags, "shiny gold"));
-}
-
-#[cfg(test)]
-mod test {
-    use crate::bag_parser;
-    use crate::calculate_day_a;
-    use crate::calculate_day_b;
-    use crate::Bag;
-    #[test]
-    fn test_parse_bag() {
-        let bag = bag_parser::parse("green bags contain 2 red bags, 4 blue bags.");
-        if let Ok(bag) = bag {
-            assert_eq!(bag.colour, "green");
-            assert_eq!(bag.contains.len(), 2);
-            assert_eq!(bag.contains[0].colour, "red");
-            assert_eq!(bag.contains[0].quantity, 2);
-            assert_eq!(bag.contains[1].colour, "blue");
-            assert_eq!(bag.contains[1].quantity, 4);
-        } else {
-            // consider testing with `cargo test --features peg/trace`
-            panic!("bag had an error, {:?}", bag);
+        return;
+    }
+    let mut unreachable = 0;
+    for problem in validate(&graph, "shiny gold") {
+        match problem {
+            RuleProblem::Unreachable(_) => unreachable += 1,
+            problem => println!("Warning: {}", problem),
         }
     }
-
-    #[test]
-    fn test_parse_bag_larger_colours() {
-        let bag = bag_parser::parse("dark green bags contain 2 dull red bags, 1 blueish grey bag.");
-        if let Ok(bag) = bag {
-            assert_eq!(bag.colour, "dark green");
-            assert_eq!(bag.contains.len(), 2);
-            assert_eq!(bag.contains[0].colour, "dull red");
-            assert_eq!(bag.contains[0].quantity, 2);
-            assert_eq!(bag.contains[1].colour, "blueish grey");
-            assert_eq!(bag.contains[1].quantity, 1);
-        } else {
-            panic!("bag had an error, {:?}", bag);
+    println!("{} bag colours are unrelated to shiny gold", unreachable);
+    let day_a = timings.time("part a", || calculate_day_a(&graph, "shiny gold"));
+    println!("Day a result: {}", day_a);
+    answers.check("a", day_a);
+    match timings.time("part b", || calculate_day_b(&graph, "shiny gold")) {
+        Ok(count) => {
+            println!("Day b result: {}", count);
+            answers.check("b", count);
         }
+        Err(problem) => println!("Day b could not be calculated: {}", problem),
     }
 
-    #[test]
-    fn test_parse_bag_no_bags_inside() {
-        let bag = bag_parser::parse("faded blue bags contain no other bags.");
-        if let Ok(bag) = bag {
-            assert_eq!(bag.colour, "faded blue");
-            assert_eq!(bag.contains.len(), 0);
-        } else {
-            panic!("bag had an error, {:?}", bag);
+    let shiny_gold = graph
+        .id("shiny gold")
+        .expect("There should be a shiny gold bag");
+    let paths = containment_paths(&graph, shiny_gold);
+    println!("{} chains of bags lead to shiny gold", paths.len());
+    if show_paths {
+        for path in paths.iter() {
+            println!("  {}", graph.colours_for(path).join(" -> "));
         }
     }
-
-    #[test]
-    fn test_day_a_problem() {
-        let lines: &str = include_str!("../test_data_a.txt");
-        let bags: Vec<Bag> = lines
-            .lines()
-            .map(bag_parser::parse)
-            .map(Result::unwrap)
-            .collect();
-        assert_eq!(calculate_day_a(&bags, "shiny gold"), 4);
+    match nesting_depth(&graph, shiny_gold) {
+        Ok(depth) => println!("Shiny gold bags nest {} levels deep", depth),
+        Err(problem) => println!("Nesting depth could not be calculated: {}", problem),
     }
-
-    #[test]
-    fn test_day_b_problem() {
-        let lines: &str = include_str!("../test_data_b.txt");
-        let bags: Vec<Bag> = lines
-            .lines()
-            .map(bag_parser::parse)
-            .map(Result::unwrap)
-            .collect();
-        assert_eq!(calculate_day_b(&bags, "shiny gold"), 126)
+    let leaves = graph.colours_for(&leaf_bags(&graph, shiny_gold));
+    println!("Innermost bags: {}", leaves.join(", "));
+    match smallest_container(&graph, shiny_gold) {
+        Some((id, total)) => println!(
+            "The smallest bag holding shiny gold is {}, holding {} bags",
+            graph.colour(id),
+            total
+        ),
+        None => println!("No bags hold shiny gold"),
+    }
+    if let Some(limit) = at_most {
+        let found = bags_holding_at_most(&graph, limit);
+        println!(
+            "Bags holding at most {} bags: {}",
+            limit,
+            graph.colours_for(&found).join(", ")
+        );
+    }
+    if let Some(copies) = exactly {
+        let found = colours_holding_exactly(&graph, copies, shiny_gold);
+        println!(
+            "Bags holding exactly {} shiny gold: {}",
+            copies,
+            graph.colours_for(&found).join(", ")
+        );
+    }
+    if show_explain {
+        match explain_day_b(&graph, shiny_gold) {
+            Ok(explanation) => print!("{}", explanation),
+            Err(problem) => println!("Day b could not be explained: {}", problem),
+        }
+    }
+    if !what_ifs.is_empty() {
+        let mut store = RuleStore::new(&parsed.bags);
+        store.total("shiny gold").ok();
+        for (i, what_if) in what_ifs.iter().enumerate() {
+            match parse_rule(i + 1, what_if) {
+                Ok(bag) => {
+                    println!("What if {}", bag);
+                    store.insert(bag);
+                }
+                Err(err) => println!("Skipping invalid what-if rule, {}", err),
+            }
+        }
+        println!("{} totals still cached", store.cached_totals());
+        match store.total("shiny gold") {
+            Ok(count) => println!("Day b result would be: {}", count),
+            Err(problem) => println!("Day b could not be calculated: {}", problem),
+        }
     }
+    timings.finish(env!("CARGO_MANIFEST_DIR"));
+    answers.finish();
 }
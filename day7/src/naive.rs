@@ -0,0 +1,158 @@
+// The original implementations, working straight off the list of parsed bags.
+// Kept around as a reference for the tests and benchmarks against `BagGraph`.
+use crate::Bag;
+use std::collections::HashMap;
+
+fn find_parents_for_colour<'a>(bags: &'a [Bag], bag_to_calculate: &str) -> Vec<&'a str> {
+    let mut ret: Vec<&'a str> = vec![];
+    for bag in bags.iter() {
+        for child_bag in bag.contains.iter() {
+            if child_bag.colour == bag_to_calculate {
+                ret.push(&bag.colour[..]);
+            }
+        }
+    }
+    ret
+}
+
+/// Linear scan version of `crate::calculate_day_a`.
+pub fn calculate_day_a(bags: &[Bag], bag_to_calculate: &str) -> usize {
+    let mut count = 0;
+    let mut bags_found: Vec<&str> = vec![bag_to_calculate];
+
+    while count < bags_found.len() {
+        let bag = bags_found[count];
+        let new_bags = find_parents_for_colour(bags, bag);
+        for new_bag in new_bags.iter() {
+            if !bags_found.contains(new_bag) {
+                bags_found.push(new_bag);
+            }
+        }
+        count += 1;
+    }
+
+    count - 1 // don't include the starting bag
+}
+
+struct BagWithCachedResult<'a> {
+    bag: &'a Bag,
+    result: Option<usize>,
+}
+
+fn get_count_for_bag_colour(
+    ret_dict: &mut HashMap<&str, BagWithCachedResult>,
+    bag_to_find: &str,
+) -> usize {
+    match (ret_dict[bag_to_find].result, ret_dict[bag_to_find].bag) {
+        (Some(ret), _) => ret,
+        (None, bag) => {
+            let mut count = 1;
+            for bag_container in bag.contains.iter() {
+                count += bag_container.quantity
+                    * get_count_for_bag_colour(ret_dict, &bag_container.colour[..]);
+            }
+            ret_dict.get_mut(bag_to_find).unwrap().result = Some(count);
+            count
+        }
+    }
+}
+
+/// HashMap memoised version of `crate::calculate_day_b`.
+pub fn calculate_day_b(bags: &[Bag], bag_to_calculate: &str) -> usize {
+    let mut ret_dict = HashMap::<&str, BagWithCachedResult>::new();
+    for bag in bags.iter() {
+        ret_dict.insert(&bag.colour[..], BagWithCachedResult { bag, result: None });
+    }
+    get_count_for_bag_colour(&mut ret_dict, bag_to_calculate) - 1 // don't include the given bag in the result
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bag_parser;
+    use crate::naive;
+    use crate::{Bag, BagGraph, BagQuantity};
+    use proptest::prelude::*;
+
+    fn parse_bags(lines: &str) -> Vec<Bag> {
+        lines
+            .lines()
+            .map(bag_parser::parse)
+            .map(Result::unwrap)
+            .collect()
+    }
+
+    #[test]
+    fn test_matches_bag_graph() {
+        let bags = parse_bags(include_str!("../input_data.txt"));
+        let graph = BagGraph::new(&bags);
+        for bag in bags.iter() {
+            assert_eq!(
+                naive::calculate_day_a(&bags, &bag.colour),
+                crate::calculate_day_a(&graph, &bag.colour),
+                "day a mismatch for {}",
+                bag.colour
+            );
+            assert_eq!(
+                naive::calculate_day_b(&bags, &bag.colour) as u128,
+                crate::calculate_day_b(&graph, &bag.colour).unwrap(),
+                "day b mismatch for {}",
+                bag.colour
+            );
+        }
+    }
+
+    fn colour(index: usize) -> String {
+        format!("shade{} hue", index)
+    }
+
+    // A rule set with no cycles, as bags only ever contain bags later in the
+    // list. Every colour that is contained has a rule of its own.
+    fn rule_set_strategy() -> impl Strategy<Value = Vec<Bag>> {
+        (1..16usize)
+            .prop_flat_map(|count| {
+                let rules: Vec<_> = (0..count)
+                    .map(|index| prop::collection::vec((index + 1..count + 1, 1..4usize), 0..4))
+                    .collect();
+                (Just(count), rules)
+            })
+            .prop_map(|(count, rules)| {
+                rules
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, contains)| Bag {
+                        colour: colour(index),
+                        contains: contains
+                            .into_iter()
+                            // `count` itself stands for no bag, so rules can be shorter
+                            .filter(|(child, _)| *child < count)
+                            .map(|(child, quantity)| BagQuantity {
+                                colour: colour(child),
+                                quantity,
+                            })
+                            .collect(),
+                    })
+                    .collect()
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn test_matches_bag_graph_generated(bags in rule_set_strategy()) {
+            let graph = BagGraph::new(&bags);
+            for bag in bags.iter() {
+                prop_assert_eq!(
+                    naive::calculate_day_a(&bags, &bag.colour),
+                    crate::calculate_day_a(&graph, &bag.colour),
+                    "day a mismatch for {}",
+                    bag.colour
+                );
+                prop_assert_eq!(
+                    Ok(naive::calculate_day_b(&bags, &bag.colour) as u128),
+                    crate::calculate_day_b(&graph, &bag.colour),
+                    "day b mismatch for {}",
+                    bag.colour
+                );
+            }
+        }
+    }
+}
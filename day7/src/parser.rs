@@ -0,0 +1,397 @@
+// Parsing the bag rules from the input text.
+use crate::{Bag, BagGraph, BagQuantity};
+
+/// A line that didn't match the grammar, with where it went wrong and what
+/// the parser would have accepted there instead.
+#[derive(Debug, PartialEq, Clone)]
+pub struct BagParseError {
+    /// 1-indexed line in the input
+    pub line: usize,
+    /// 1-indexed column within that line, counting characters (not bytes)
+    pub column: usize,
+    pub expected: Vec<String>,
+    pub text: String,
+}
+
+impl BagParseError {
+    fn new(line: usize, text: &str, err: peg::error::ParseError<peg::str::LineCol>) -> Self {
+        let mut expected: Vec<String> = err.expected.tokens().map(String::from).collect();
+        expected.sort();
+        BagParseError {
+            line,
+            column: err.location.column,
+            expected,
+            text: String::from(text),
+        }
+    }
+}
+
+impl std::fmt::Display for BagParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let found = self
+            .text
+            .chars()
+            .nth(self.column - 1)
+            .map_or(String::from("end of line"), |c| format!("{:?}", c));
+        // Tabs are kept so the caret lines up with the text above it
+        let padding: String = self
+            .text
+            .chars()
+            .chain(std::iter::repeat(' '))
+            .take(self.column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        writeln!(
+            f,
+            "line {}, column {}: found {}, expected {}",
+            self.line,
+            self.column,
+            found,
+            match self.expected.len() {
+                1 => self.expected[0].clone(),
+                _ => format!("one of {}", self.expected.join(", ")),
+            }
+        )?;
+        writeln!(f, "  {}", self.text)?;
+        write!(f, "  {}^", padding)
+    }
+}
+
+/// Parse a single rule, reporting errors against the given 1-indexed line number
+pub fn parse_rule(line_number: usize, line: &str) -> Result<Bag, BagParseError> {
+    bag_parser::parse(line).map_err(|err| BagParseError::new(line_number, line, err))
+}
+
+/// The result of parsing a whole file: every rule that parsed, ready for
+/// `BagGraph::new`, and a diagnostic for every line that didn't.
+#[derive(Debug, Default)]
+pub struct ParsedRules {
+    pub bags: Vec<Bag>,
+    pub diagnostics: Vec<BagParseError>,
+}
+
+impl ParsedRules {
+    pub fn is_ok(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+
+    pub fn graph(&self) -> BagGraph {
+        BagGraph::new(&self.bags)
+    }
+}
+
+/// Parse every rule in a file, one per line, ignoring blank lines.
+/// Lines that fail don't stop the parse, they are collected into the diagnostics.
+pub fn parse_rules(input: &str) -> ParsedRules {
+    let lines = bag_parser::rules(input).expect("Every line is either a rule, blank, or invalid");
+    let mut parsed = ParsedRules::default();
+    for (i, line) in lines.into_iter().enumerate() {
+        match line {
+            Line::Blank => (),
+            Line::Rule(bag) => parsed.bags.push(bag),
+            // Parse the line on its own again to find out why it failed
+            Line::Invalid(text) => match parse_rule(i + 1, text) {
+                Err(err) => parsed.diagnostics.push(err),
+                // The two grammars disagree, which is a bug here rather than
+                // in the input, but still shouldn't take the program down
+                Ok(_) => parsed.diagnostics.push(BagParseError {
+                    line: i + 1,
+                    column: 1,
+                    expected: vec![String::from("rule that also parses within the file")],
+                    text: String::from(text),
+                }),
+            },
+        }
+    }
+    parsed
+}
+
+/// A single line of a file of rules, as parsed by `bag_parser::rules`
+#[derive(Debug)]
+pub enum Line<'a> {
+    Blank,
+    Rule(Bag),
+    Invalid(&'a str),
+}
+
+// Parse the each input line into a Bag object.
+// Bags have a colour, and can contain other bags of given colours.
+//
+// The line to parse follows this format:
+// green bags contain 4 red bags.
+// red bags contain 2 blue bags, 1 yellow bag, 5 purple yellow bags.
+// blue bags contain no other bags.
+// yellow bags contain 1 shiny blue bag.
+// purple yellow bags contain no other bags.
+// shiny blue bags contain no other bags.
+//
+// Keywords and colours are case insensitive (colours are stored in lowercase),
+// and any run of spaces/tabs is accepted where the format has a single space.
+// Colours are made of words of letters, so can't contain digits, or the word "bag(s)".
+peg::parser! { pub grammar bag_parser() for str {
+    rule _() = quiet!{[' ' | '\t']*}
+
+    rule __() = quiet!{[' ' | '\t']+} / expected!("space")
+
+    // Matches the literal case insensitively, reporting errors at the start of it
+    rule i(literal: &'static str)
+        = quiet!{
+            text:$([_]*<{literal.chars().count()}>) {?
+                if text.eq_ignore_ascii_case(literal) { Ok(()) } else { Err(literal) }
+            }
+        } / {? Err(literal) }
+
+    // The digits are looked at before they're consumed, so a number that's
+    // too big is reported at its start rather than its end
+    rule digits() -> &'input str = $(['0'..='9']+)
+
+    rule number() -> usize
+        = n:(&digits()) count:({?
+            n.parse().or(Err("number small enough to fit in a usize"))
+        }) digits() { count }
+        / !digits() {? Err("number") }
+
+    rule bag_word() = i("bag") i("s")? !['a'..='z' | 'A'..='Z']
+
+    rule word() -> &'input str
+        = !bag_word() w:$(quiet!{['a'..='z' | 'A'..='Z']+} / expected!("colour")) { w }
+
+    rule colour() -> String
+        = words:word() ++ __() { words.join(" ").to_lowercase() }
+
+    rule bag_quantity() -> BagQuantity
+        = count:number() __ colour:colour() __ bag_word() {
+            BagQuantity{ colour, quantity: count }
+        }
+
+    rule inner_bags() -> Vec<BagQuantity>
+        = b:bag_quantity() ++ (_ "," _) { b }
+
+    rule no_other_bags() -> Vec<BagQuantity>
+        = i("no") __ i("other") __ bag_word() { vec![] }
+
+    rule end() = _ "."? quiet!{[' ' | '\t' | '\r' | '\n']*} ![_]
+
+    // `end()` for a line in a file: the same trailing whitespace and stray
+    // '\r's are allowed, but the newline is left for `rules()` to split on
+    rule line_end() = _ "."? quiet!{[' ' | '\t' | '\r']*} &("\n" / ![_])
+
+    rule bag_rule() -> Bag
+        = _ colour:colour() __ bag_word() __ i("contain") __ contains:(no_other_bags() / inner_bags()) _ "."? _ {
+            Bag{ colour, contains }
+        }
+
+    pub rule parse() -> Bag
+        = bag:bag_rule() end() { bag }
+
+    rule eol() = "\r"? "\n"
+
+    rule line() -> Line<'input>
+        = quiet!{[' ' | '\t' | '\r']*} &("\n" / ![_]) { Line::Blank }
+        / bag:bag_rule() line_end() { Line::Rule(bag) }
+        / text:$((!eol() [_])+) { Line::Invalid(text) }
+
+    // A whole file of rules. Never fails, as lines that aren't rules become `Line::Invalid`.
+    pub rule rules() -> Vec<Line<'input>>
+        = lines:line() ** eol() { lines }
+}}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_extra_whitespace() {
+        let bag = bag_parser::parse(
+            "  dark   green\tbags contain 2  dull red bags ,1 blueish  grey bag . ",
+        )
+        .unwrap();
+        assert_eq!(
+            bag.to_string(),
+            "dark green bags contain 2 dull red bags, 1 blueish grey bag."
+        );
+    }
+
+    #[test]
+    fn test_case_insensitive() {
+        let bag = bag_parser::parse("Dark Green BAGS Contain 2 DULL red Bags.").unwrap();
+        assert_eq!(bag.to_string(), "dark green bags contain 2 dull red bags.");
+        let bag = bag_parser::parse("Faded Blue bags contain No Other Bags.").unwrap();
+        assert_eq!(bag.to_string(), "faded blue bags contain no other bags.");
+    }
+
+    #[test]
+    fn test_trailing_newlines() {
+        for line in [
+            "red bags contain no other bags.\n",
+            "red bags contain no other bags.\r\n",
+            "red bags contain no other bags\n\n",
+            "red bags contain 1 blue bag.\r\n",
+        ] {
+            assert!(bag_parser::parse(line).is_ok(), "{:?} should parse", line);
+        }
+    }
+
+    #[test]
+    fn test_colour_words_starting_with_bag() {
+        let bag = bag_parser::parse("bagel bags contain 1 baggy red bag.").unwrap();
+        assert_eq!(bag.colour, "bagel");
+        assert_eq!(bag.contains[0].colour, "baggy red");
+    }
+
+    #[test]
+    fn test_digits_in_colour() {
+        let err = parse_rule(1, "dark 3 red bags contain no other bags.").unwrap_err();
+        assert_eq!(err.column, 6);
+        assert!(err.expected.contains(&String::from("colour")));
+        let err = parse_rule(1, "red bags contain 3 dark2 red bags.").unwrap_err();
+        assert_eq!(err.column, 24);
+    }
+
+    #[test]
+    fn test_number_overflow() {
+        let err =
+            parse_rule(1, "red bags contain 99999999999999999999999999 blue bags.").unwrap_err();
+        // Reported at the start of the number, where "no other bags" could also have gone
+        assert_eq!(err.column, 18);
+        assert_eq!(
+            err.expected,
+            ["no", "number small enough to fit in a usize"]
+        );
+        let err = parse_rule(1, "red bags contain some blue bags.").unwrap_err();
+        assert_eq!(err.column, 18);
+        assert!(err.expected.contains(&String::from("number")));
+    }
+
+    #[test]
+    fn test_error_display() {
+        let err = parse_rule(3, "red bags hold 2 blue bags.").unwrap_err();
+        assert_eq!(err.line, 3);
+        assert_eq!(err.column, 10);
+        assert_eq!(
+            err.to_string(),
+            "line 3, column 10: found 'h', expected contain\n\
+             \x20 red bags hold 2 blue bags.\n\
+             \x20          ^"
+        );
+    }
+
+    #[test]
+    fn test_error_display_non_ascii() {
+        let err = BagParseError {
+            line: 1,
+            column: 10,
+            expected: vec![String::from("contain")],
+            text: String::from("rød bags hold 2 blue bags."),
+        };
+        assert_eq!(
+            err.to_string(),
+            "line 1, column 10: found 'h', expected contain\n\
+             \x20 rød bags hold 2 blue bags.\n\
+             \x20          ^"
+        );
+        let err = parse_rule(1, "red\tbags contain 1 blé bag.").unwrap_err();
+        assert_eq!(err.column, 22);
+        assert!(err.to_string().ends_with(
+            "found 'é', expected space\n  red\tbags contain 1 blé bag.\n     \t                 ^"
+        ));
+    }
+
+    #[test]
+    fn test_error_at_end_of_line() {
+        let err = parse_rule(1, "red bags contain").unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("line 1, column 17: found end of line"));
+    }
+
+    #[test]
+    fn test_parse_rules() {
+        let rules = "red bags contain 1 blue bag.\n\nblue bags contain no other bags.\n";
+        let parsed = parse_rules(rules);
+        assert!(parsed.is_ok());
+        assert_eq!(
+            parsed.bags,
+            [
+                bag_parser::parse("red bags contain 1 blue bag.").unwrap(),
+                bag_parser::parse("blue bags contain no other bags.").unwrap(),
+            ]
+        );
+        assert_eq!(parsed.graph().len(), 2);
+    }
+
+    #[test]
+    fn test_parse_rules_input_data() {
+        let parsed = parse_rules(include_str!("../input_data.txt"));
+        assert!(parsed.is_ok());
+        assert_eq!(
+            parsed.bags.len(),
+            include_str!("../input_data.txt").lines().count()
+        );
+    }
+
+    #[test]
+    fn test_parse_rules_blank_and_windows_lines() {
+        let parsed = parse_rules(
+            "\r\n  \nred bags contain 1 blue bag.\r\n\r\nblue bags contain no other bags.",
+        );
+        assert!(parsed.is_ok());
+        assert_eq!(parsed.bags.len(), 2);
+        assert!(parse_rules("").is_ok());
+    }
+
+    #[test]
+    fn test_parse_rules_stray_carriage_returns() {
+        let parsed = parse_rules("faded blue bags contain no other bags.\r");
+        assert!(parsed.is_ok());
+        assert_eq!(parsed.bags.len(), 1);
+        let parsed = parse_rules("faded blue bags contain no other bags.\r\r\nx");
+        assert_eq!(parsed.bags.len(), 1);
+        assert_eq!(parsed.diagnostics.len(), 1);
+        assert_eq!(parsed.diagnostics[0].line, 2);
+        let parsed = parse_rules(
+            "red bags contain 1 blue bag .. \t\r\n\r\r\nblue bags contain no other bags",
+        );
+        assert!(parsed.is_ok());
+        assert_eq!(parsed.bags.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_rules_collects_all_errors() {
+        let parsed = parse_rules(
+            "red bags contain 1 blue bag.\n\
+             \n\
+             blue bags contain nothing.\n\
+             green bags contain 2 red bags.\n\
+             dark 3 red bags contain no other bags.\r\n\
+             white bags contain 1 red bag and 2 blue bags.",
+        );
+        assert!(!parsed.is_ok());
+        assert_eq!(parsed.bags.len(), 2);
+        let locations: Vec<(usize, usize)> = parsed
+            .diagnostics
+            .iter()
+            .map(|err| (err.line, err.column))
+            .collect();
+        assert_eq!(locations, [(3, 21), (5, 6), (6, 30)]);
+        assert_eq!(parsed.diagnostics[0].text, "blue bags contain nothing.");
+        assert_eq!(
+            parsed.diagnostics[1].text,
+            "dark 3 red bags contain no other bags."
+        );
+    }
+
+    proptest! {
+        // Whatever follows a rule, the file grammar must agree with `parse`
+        #[test]
+        fn test_parse_rules_agrees_with_parse(suffix in "[ .\t\r\nx]{0,6}") {
+            let text = format!("faded blue bags contain no other bags{}", suffix);
+            let parsed = parse_rules(&text);
+            if let Ok(bag) = parse_rule(1, &text) {
+                prop_assert!(parsed.is_ok(), "{:?}", parsed.diagnostics);
+                prop_assert_eq!(parsed.bags, [bag]);
+            }
+        }
+    }
+}
@@ -0,0 +1,391 @@
+// Queries that explain the answers, rather than just giving a count or a total.
+use crate::bag_graph::{BagGraph, ColourId};
+use crate::validation::RuleProblem;
+
+/// Every chain of bags from an outermost bag (one that nothing else holds)
+/// down to the given colour, e.g. [light red, muted yellow, shiny gold].
+/// Bags that would repeat within a chain (a cycle) are skipped.
+pub fn containment_paths(graph: &BagGraph, id: ColourId) -> Vec<Vec<ColourId>> {
+    let mut paths = Vec::<Vec<ColourId>>::new();
+    let mut path = vec![id];
+    find_paths_upwards(graph, &mut path, &mut paths);
+    paths
+}
+
+// `path` is built inner bag first, and reversed when it reaches the top
+fn find_paths_upwards(graph: &BagGraph, path: &mut Vec<ColourId>, paths: &mut Vec<Vec<ColourId>>) {
+    let current = *path.last().unwrap();
+    let parents = graph.parents(current);
+    if parents.is_empty() {
+        if path.len() > 1 {
+            paths.push(path.iter().rev().copied().collect());
+        }
+        return;
+    }
+    for (parent, _) in parents.iter() {
+        if !path.contains(parent) {
+            path.push(*parent);
+            find_paths_upwards(graph, path, paths);
+            path.pop();
+        }
+    }
+}
+
+/// The most levels of bags inside the given colour. A bag that holds
+/// nothing has a depth of 0, one that holds only those has a depth of 1, etc.
+pub fn nesting_depth(graph: &BagGraph, id: ColourId) -> Result<usize, RuleProblem> {
+    graph.fold_contained(id, &|_, children: &[(usize, usize)]| {
+        children
+            .iter()
+            .map(|(depth, _)| depth + 1)
+            .max()
+            .unwrap_or(0)
+    })
+}
+
+/// The colours somewhere inside the given colour that don't hold any other bags
+pub fn leaf_bags(graph: &BagGraph, id: ColourId) -> Vec<ColourId> {
+    graph
+        .descendants(id)
+        .into_iter()
+        .filter(|descendant| graph.children(*descendant).is_empty())
+        .collect()
+}
+
+/// Write out the whole multiplication tree behind `calculate_day_b`, one line
+/// per bag, showing how many of it there are, how many bags each of them
+/// holds, and what that adds to the total. e.g. for the website's example:
+///
+/// ```text
+/// shiny gold holds 126 bags
+///   2 dark red = 2 x (1 + 62) = 126
+///     2 dark orange = 2 x (1 + 30) = 62
+/// ```
+///
+/// A bag that was already broken down further up is only written once, and
+/// any later lines for it end in "(see above)" instead of repeating its bags.
+pub fn explain_day_b(graph: &BagGraph, id: ColourId) -> Result<String, RuleProblem> {
+    let counts = graph.count_all();
+    let total = counts[id].clone()?;
+    let mut ret = format!("{} holds {} bags\n", graph.colour(id), total);
+    let mut shown = vec![false; graph.len()];
+    shown[id] = true;
+    explain_children(graph, &counts, id, 1, &mut shown, &mut ret)?;
+    Ok(ret)
+}
+
+fn explain_children(
+    graph: &BagGraph,
+    counts: &[Result<u128, RuleProblem>],
+    id: ColourId,
+    depth: usize,
+    shown: &mut Vec<bool>,
+    ret: &mut String,
+) -> Result<(), RuleProblem> {
+    for (child, quantity) in graph.children(id).iter() {
+        let held = counts[*child].clone()?;
+        let repeated = shown[*child] && !graph.children(*child).is_empty();
+        *ret += &format!(
+            "{}{} {} = {} x (1 + {}) = {}{}\n",
+            "  ".repeat(depth),
+            quantity,
+            graph.colour(*child),
+            quantity,
+            held,
+            (1 + held) * *quantity as u128,
+            if repeated { " (see above)" } else { "" }
+        );
+        if !repeated {
+            shown[*child] = true;
+            explain_children(graph, counts, *child, depth + 1, shown, ret)?;
+        }
+    }
+    Ok(())
+}
+
+/// Every colour (with a rule) holding at most `limit` bags in total, i.e.
+/// the bags that could be used as the outermost bag within that capacity.
+/// A total too big to count is over any limit, and colours whose total can't
+/// be worked out at all (a cycle or a missing rule) are left out.
+pub fn bags_holding_at_most(graph: &BagGraph, limit: u128) -> Vec<ColourId> {
+    graph
+        .count_all()
+        .into_iter()
+        .enumerate()
+        .filter(|(id, total)| {
+            graph.is_defined(*id) && matches!(total, Ok(total) if *total <= limit)
+        })
+        .map(|(id, _)| id)
+        .collect()
+}
+
+/// Of the bags that eventually hold the given colour, the one holding the
+/// fewest bags in total, and that total. `None` if nothing holds it. Bags
+/// whose total can't be counted are skipped, as they are either too big to
+/// be the smallest or broken.
+pub fn smallest_container(graph: &BagGraph, id: ColourId) -> Option<(ColourId, u128)> {
+    let totals = graph.count_all();
+    graph
+        .ancestors(id)
+        .into_iter()
+        .filter_map(|ancestor| match totals[ancestor] {
+            Ok(total) => Some((ancestor, total)),
+            Err(_) => None,
+        })
+        .min_by_key(|(_, total)| *total)
+}
+
+/// How many bags of the `target` colour are somewhere inside one bag of the
+/// given colour, counting through every layer of bags in between.
+pub fn copies_held(graph: &BagGraph, id: ColourId, target: ColourId) -> Result<u128, RuleProblem> {
+    graph.fold_contained(id, &|parent, children| {
+        count_copies(graph, target, parent, children)
+    })?
+}
+
+// The copies of `target` inside `parent`, from the copies inside each bag it directly holds
+fn count_copies(
+    graph: &BagGraph,
+    target: ColourId,
+    parent: ColourId,
+    children: &[(Result<u128, RuleProblem>, usize)],
+) -> Result<u128, RuleProblem> {
+    let mut copies = 0u128;
+    for ((child, _), (count, quantity)) in graph.children(parent).iter().zip(children) {
+        let direct = if *child == target { 1 } else { 0 };
+        copies = (count.clone()? + direct)
+            .checked_mul(*quantity as u128)
+            .and_then(|count| copies.checked_add(count))
+            .ok_or_else(|| RuleProblem::Overflow {
+                colour: String::from(graph.colour(parent)),
+                holds: String::from(graph.colour(*child)),
+                quantity: *quantity,
+            })?;
+    }
+    Ok(copies)
+}
+
+/// Every colour (with a rule) that holds exactly `copies` bags of the
+/// `target` colour, directly or inside other bags. Colours whose count
+/// overflows or can't be worked out are left out.
+pub fn colours_holding_exactly(graph: &BagGraph, copies: u128, target: ColourId) -> Vec<ColourId> {
+    graph
+        .fold_all(&|parent, children| count_copies(graph, target, parent, children))
+        .into_iter()
+        .enumerate()
+        .filter(|(id, held)| {
+            graph.is_defined(*id) && matches!(held, Ok(Ok(held)) if *held == copies)
+        })
+        .map(|(id, _)| id)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{bag_parser, Bag};
+
+    fn parse_graph(lines: &str) -> BagGraph {
+        let bags: Vec<Bag> = lines
+            .lines()
+            .map(bag_parser::parse)
+            .map(Result::unwrap)
+            .collect();
+        BagGraph::new(&bags)
+    }
+
+    fn colours(graph: &BagGraph, ids: &[ColourId]) -> Vec<String> {
+        graph.colours_for(ids)
+    }
+
+    #[test]
+    fn test_containment_paths() {
+        let graph = parse_graph(include_str!("../test_data_a.txt"));
+        let shiny_gold = graph.id("shiny gold").unwrap();
+        let mut paths: Vec<Vec<String>> = containment_paths(&graph, shiny_gold)
+            .iter()
+            .map(|path| colours(&graph, path))
+            .collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            [
+                ["dark orange", "bright white", "shiny gold"],
+                ["dark orange", "muted yellow", "shiny gold"],
+                ["light red", "bright white", "shiny gold"],
+                ["light red", "muted yellow", "shiny gold"],
+            ]
+        );
+        let light_red = graph.id("light red").unwrap();
+        assert!(containment_paths(&graph, light_red).is_empty());
+    }
+
+    #[test]
+    fn test_containment_paths_with_cycle() {
+        let graph = parse_graph(
+            "red bags contain 1 blue bag.\n\
+             blue bags contain 1 red bag, 1 gold bag.\n\
+             white bags contain 1 blue bag.\n\
+             gold bags contain no other bags.",
+        );
+        let paths: Vec<Vec<String>> = containment_paths(&graph, graph.id("gold").unwrap())
+            .iter()
+            .map(|path| colours(&graph, path))
+            .collect();
+        assert_eq!(paths, [["white", "blue", "gold"]]);
+    }
+
+    #[test]
+    fn test_nesting_depth() {
+        let graph = parse_graph(include_str!("../test_data_b.txt"));
+        assert_eq!(
+            nesting_depth(&graph, graph.id("shiny gold").unwrap()),
+            Ok(6)
+        );
+        assert_eq!(
+            nesting_depth(&graph, graph.id("dark violet").unwrap()),
+            Ok(0)
+        );
+        let graph = parse_graph(include_str!("../test_data_a.txt"));
+        assert_eq!(nesting_depth(&graph, graph.id("light red").unwrap()), Ok(4));
+    }
+
+    #[test]
+    fn test_nesting_depth_cycle() {
+        let graph = parse_graph("red bags contain 1 red bag.");
+        assert!(nesting_depth(&graph, 0).is_err());
+    }
+
+    #[test]
+    fn test_leaf_bags() {
+        let graph = parse_graph(include_str!("../test_data_a.txt"));
+        let mut leaves = colours(&graph, &leaf_bags(&graph, graph.id("shiny gold").unwrap()));
+        leaves.sort();
+        assert_eq!(leaves, ["dotted black", "faded blue"]);
+        assert!(leaf_bags(&graph, graph.id("faded blue").unwrap()).is_empty());
+    }
+
+    #[test]
+    fn test_explain_day_b() {
+        let graph = parse_graph(include_str!("../test_data_b.txt"));
+        let explanation = explain_day_b(&graph, graph.id("shiny gold").unwrap()).unwrap();
+        assert_eq!(
+            explanation,
+            "shiny gold holds 126 bags\n\
+             \x20 2 dark red = 2 x (1 + 62) = 126\n\
+             \x20   2 dark orange = 2 x (1 + 30) = 62\n\
+             \x20     2 dark yellow = 2 x (1 + 14) = 30\n\
+             \x20       2 dark green = 2 x (1 + 6) = 14\n\
+             \x20         2 dark blue = 2 x (1 + 2) = 6\n\
+             \x20           2 dark violet = 2 x (1 + 0) = 2\n"
+        );
+    }
+
+    #[test]
+    fn test_explain_day_b_branches() {
+        let graph = parse_graph(include_str!("../test_data_a.txt"));
+        let explanation = explain_day_b(&graph, graph.id("shiny gold").unwrap()).unwrap();
+        let lines: Vec<&str> = explanation.lines().collect();
+        assert_eq!(lines[0], "shiny gold holds 32 bags");
+        assert_eq!(lines[1], "  1 dark olive = 1 x (1 + 7) = 8");
+        assert_eq!(lines[2], "    3 faded blue = 3 x (1 + 0) = 3");
+        assert_eq!(lines[4], "  2 vibrant plum = 2 x (1 + 11) = 24");
+        assert_eq!(lines.len(), 7);
+    }
+
+    #[test]
+    fn test_explain_day_b_shared_bags() {
+        let graph = parse_graph(
+            "top bags contain 2 left bags, 3 right bags.\n\
+             left bags contain 1 shared bag.\n\
+             right bags contain 2 shared bags.\n\
+             shared bags contain 4 leaf bags.\n\
+             leaf bags contain no other bags.",
+        );
+        let explanation = explain_day_b(&graph, graph.id("top").unwrap()).unwrap();
+        assert_eq!(
+            explanation,
+            "top holds 45 bags\n\
+             \x20 2 left = 2 x (1 + 5) = 12\n\
+             \x20   1 shared = 1 x (1 + 4) = 5\n\
+             \x20     4 leaf = 4 x (1 + 0) = 4\n\
+             \x20 3 right = 3 x (1 + 10) = 33\n\
+             \x20   2 shared = 2 x (1 + 4) = 10 (see above)\n"
+        );
+    }
+
+    #[test]
+    fn test_bags_holding_at_most() {
+        let graph = parse_graph(include_str!("../test_data_a.txt"));
+        let mut found = colours(&graph, &bags_holding_at_most(&graph, 11));
+        found.sort();
+        assert_eq!(
+            found,
+            ["dark olive", "dotted black", "faded blue", "vibrant plum"]
+        );
+        assert_eq!(bags_holding_at_most(&graph, 1000).len(), 9);
+    }
+
+    #[test]
+    fn test_bags_holding_at_most_skips_broken_colours() {
+        let graph = parse_graph(
+            "huge bags contain 18000000000000000000 big bags.\n\
+             big bags contain 18000000000000000000 small bags.\n\
+             loop bags contain 1 loop bag.\n\
+             missing bags contain 1 unknown bag.\n\
+             small bags contain 2 tiny bags.\n\
+             tiny bags contain no other bags.",
+        );
+        assert_eq!(
+            colours(&graph, &bags_holding_at_most(&graph, 10)),
+            ["small", "tiny"]
+        );
+        assert_eq!(
+            colours(&graph, &bags_holding_at_most(&graph, u128::MAX)),
+            ["big", "small", "tiny"]
+        );
+        let tiny = graph.id("tiny").unwrap();
+        assert_eq!(
+            colours(&graph, &colours_holding_exactly(&graph, 2, tiny)),
+            ["small"]
+        );
+        assert_eq!(
+            smallest_container(&graph, tiny),
+            Some((graph.id("small").unwrap(), 2))
+        );
+    }
+
+    #[test]
+    fn test_smallest_container() {
+        let graph = parse_graph(include_str!("../test_data_a.txt"));
+        let bright_white = graph.id("bright white").unwrap();
+        assert_eq!(
+            smallest_container(&graph, graph.id("shiny gold").unwrap()),
+            Some((bright_white, 33))
+        );
+        assert_eq!(
+            smallest_container(&graph, graph.id("light red").unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_colours_holding_exactly() {
+        let graph = parse_graph(include_str!("../test_data_a.txt"));
+        let shiny_gold = graph.id("shiny gold").unwrap();
+        assert_eq!(
+            copies_held(&graph, graph.id("dark orange").unwrap(), shiny_gold),
+            Ok(11)
+        );
+        assert_eq!(
+            colours(&graph, &colours_holding_exactly(&graph, 5, shiny_gold)),
+            ["light red"]
+        );
+        assert_eq!(
+            colours(&graph, &colours_holding_exactly(&graph, 1, shiny_gold)),
+            ["bright white"]
+        );
+        let faded_blue = graph.id("faded blue").unwrap();
+        assert_eq!(copies_held(&graph, shiny_gold, faded_blue), Ok(3 + 2 * 5));
+    }
+}
@@ -0,0 +1,263 @@
+// A set of bag rules that can be edited one rule at a time, keeping the part b
+// totals cached between edits. An edit only throws away the cached totals of
+// the edited colour and the bags that (eventually) hold it, so trying out
+// "what if" changes on a large rule set doesn't recalculate everything.
+use crate::count::BagCount;
+use crate::validation::RuleProblem;
+use crate::Bag;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Default)]
+pub struct RuleStore {
+    rules: HashMap<String, Bag>,
+    /// colour -> the colours with a rule that directly holds it
+    held_by: HashMap<String, HashSet<String>>,
+    /// colour -> total bags including itself, for colours calculated since their last change
+    totals: HashMap<String, u128>,
+}
+
+impl RuleStore {
+    pub fn new(bags: &[Bag]) -> RuleStore {
+        let mut store = RuleStore::default();
+        for bag in bags.iter() {
+            store.insert(bag.clone());
+        }
+        store
+    }
+
+    pub fn get(&self, colour: &str) -> Option<&Bag> {
+        self.rules.get(colour)
+    }
+
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// How many colours currently have a cached total
+    pub fn cached_totals(&self) -> usize {
+        self.totals.len()
+    }
+
+    /// Add a rule, or replace the existing rule for that colour.
+    /// Returns the rule that was replaced, if any.
+    pub fn insert(&mut self, bag: Bag) -> Option<Bag> {
+        let previous = self.remove(&bag.colour);
+        for child in bag.contains.iter() {
+            self.held_by
+                .entry(child.colour.clone())
+                .or_default()
+                .insert(bag.colour.clone());
+        }
+        self.rules.insert(bag.colour.clone(), bag);
+        previous
+    }
+
+    /// Remove the rule for a colour, returning it if there was one
+    pub fn remove(&mut self, colour: &str) -> Option<Bag> {
+        let previous = self.rules.remove(colour)?;
+        for child in previous.contains.iter() {
+            if let Some(parents) = self.held_by.get_mut(&child.colour) {
+                parents.remove(colour);
+            }
+        }
+        self.invalidate(colour);
+        Some(previous)
+    }
+
+    // Forget the cached totals of the colour and everything that holds it.
+    // A colour without a cached total can't have ancestors with one through
+    // it (their totals would have needed it), so we stop searching there.
+    fn invalidate(&mut self, colour: &str) {
+        let mut to_check = vec![String::from(colour)];
+        while let Some(colour) = to_check.pop() {
+            if self.totals.remove(&colour).is_some() {
+                if let Some(parents) = self.held_by.get(&colour) {
+                    to_check.extend(parents.iter().cloned());
+                }
+            }
+        }
+    }
+
+    /// Total number of bags held inside one bag of the given colour, the same
+    /// as `crate::calculate_day_b` but reusing totals from previous calls.
+    pub fn total(&mut self, colour: &str) -> Result<u128, RuleProblem> {
+        let mut path = vec![];
+        Ok(self.total_with_cache(colour, &mut path)? - 1) // don't include the given bag in the result
+    }
+
+    // The total including the bag itself. `path` is the chain of bags we are
+    // currently inside of, used to report cycles.
+    fn total_with_cache(
+        &mut self,
+        colour: &str,
+        path: &mut Vec<String>,
+    ) -> Result<u128, RuleProblem> {
+        if let Some(total) = self.totals.get(colour) {
+            return Ok(*total);
+        }
+        if let Some(start) = path.iter().position(|bag| bag == colour) {
+            let mut cycle = path[start..].to_vec();
+            cycle.push(String::from(colour));
+            return Err(RuleProblem::Cycle(cycle));
+        }
+        let children: Vec<(String, usize)> = match self.rules.get(colour) {
+            Some(bag) => bag
+                .contains
+                .iter()
+                .map(|child| (child.colour.clone(), child.quantity))
+                .collect(),
+            None => {
+                return Err(RuleProblem::UndefinedColour {
+                    colour: String::from(colour),
+                    used_by: path.last().into_iter().cloned().collect(),
+                })
+            }
+        };
+        path.push(String::from(colour));
+        let mut total = 1u128;
+        for (child, quantity) in children.iter() {
+            total = self
+                .total_with_cache(child, path)?
+                .mul_quantity(*quantity)
+                .and_then(|count| total.add_count(&count))
+                .ok_or_else(|| RuleProblem::Overflow {
+                    colour: String::from(colour),
+                    holds: child.clone(),
+                    quantity: *quantity,
+                })?;
+        }
+        path.pop();
+        self.totals.insert(String::from(colour), total);
+        Ok(total)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bag_parser;
+    use crate::parser::parse_rules;
+
+    fn test_store() -> RuleStore {
+        RuleStore::new(&parse_rules(include_str!("../test_data_b.txt")).bags)
+    }
+
+    #[test]
+    fn test_total() {
+        let mut store = test_store();
+        assert_eq!(store.len(), 7);
+        assert_eq!(store.total("shiny gold"), Ok(126));
+        assert_eq!(store.cached_totals(), 7);
+        assert_eq!(store.total("dark green"), Ok(6));
+    }
+
+    #[test]
+    fn test_matches_calculate_day_b() {
+        let parsed = parse_rules(include_str!("../input_data.txt"));
+        let graph = parsed.graph();
+        let mut store = RuleStore::new(&parsed.bags);
+        for bag in parsed.bags.iter() {
+            assert_eq!(
+                store.total(&bag.colour),
+                crate::calculate_day_b(&graph, &bag.colour)
+            );
+        }
+    }
+
+    #[test]
+    fn test_modify_only_invalidates_ancestors() {
+        let mut store = test_store();
+        store.total("shiny gold").unwrap();
+        let previous =
+            store.insert(bag_parser::parse("dark green bags contain 3 dark blue bags.").unwrap());
+        assert_eq!(
+            previous,
+            Some(bag_parser::parse("dark green bags contain 2 dark blue bags.").unwrap())
+        );
+        // dark blue and dark violet are still cached, everything holding dark green is not
+        assert_eq!(store.cached_totals(), 2);
+        // dark green now holds 3 * (1 + 2) = 9 bags, so each level up doubles (1 + that)
+        assert_eq!(store.total("dark green"), Ok(9));
+        assert_eq!(store.total("shiny gold"), Ok(174));
+        assert_eq!(store.cached_totals(), 7);
+    }
+
+    #[test]
+    fn test_add_and_remove() {
+        let mut store = test_store();
+        assert_eq!(store.total("dark blue"), Ok(2));
+        assert_eq!(
+            store.insert(bag_parser::parse("dark violet bags contain 4 pale red bags.").unwrap()),
+            Some(bag_parser::parse("dark violet bags contain no other bags.").unwrap())
+        );
+        assert_eq!(store.cached_totals(), 0);
+        assert!(store.total("dark blue").is_err());
+        assert_eq!(
+            store.insert(bag_parser::parse("pale red bags contain no other bags.").unwrap()),
+            None
+        );
+        assert_eq!(store.total("dark blue"), Ok(10));
+        assert!(store.remove("pale red").is_some());
+        assert_eq!(store.get("pale red"), None);
+        assert_eq!(
+            store.total("dark blue"),
+            Err(RuleProblem::UndefinedColour {
+                colour: String::from("pale red"),
+                used_by: vec![String::from("dark violet")]
+            })
+        );
+        assert_eq!(store.remove("pale red"), None);
+    }
+
+    #[test]
+    fn test_unrelated_edit_keeps_cache() {
+        let mut store = test_store();
+        store.total("shiny gold").unwrap();
+        store.insert(bag_parser::parse("plain white bags contain 1 dark red bag.").unwrap());
+        assert_eq!(store.cached_totals(), 7);
+        assert_eq!(store.total("plain white"), Ok(63));
+    }
+
+    #[test]
+    fn test_cycle() {
+        let mut store = test_store();
+        store.insert(bag_parser::parse("dark violet bags contain 1 dark blue bag.").unwrap());
+        assert_eq!(
+            store.total("dark blue"),
+            Err(RuleProblem::Cycle(vec![
+                String::from("dark blue"),
+                String::from("dark violet"),
+                String::from("dark blue")
+            ]))
+        );
+        store.insert(bag_parser::parse("dark violet bags contain no other bags.").unwrap());
+        assert_eq!(store.total("shiny gold"), Ok(126));
+    }
+
+    #[test]
+    fn test_overflow() {
+        let mut store = RuleStore::default();
+        let layers: Vec<String> = ('a'..='n').map(|c| format!("layer {}", c)).collect();
+        for pair in layers.windows(2) {
+            let rule = format!("{} bags contain 1000 {} bags.", pair[0], pair[1]);
+            store.insert(bag_parser::parse(&rule).unwrap());
+        }
+        store.insert(bag_parser::parse("layer n bags contain no other bags.").unwrap());
+        assert_eq!(
+            store.total("layer a"),
+            Err(RuleProblem::Overflow {
+                colour: String::from("layer a"),
+                holds: String::from("layer b"),
+                quantity: 1000
+            })
+        );
+        assert_eq!(
+            store.total("layer b").map(|total| total.to_string()),
+            Ok(String::from("1001001001001001001001001001001001000"))
+        );
+    }
+}
@@ -0,0 +1,243 @@
+// Sanity checks over a set of bag rules, so bad input gets reported up front
+// rather than overflowing the stack or panicking halfway through a calculation.
+use crate::bag_graph::{BagGraph, ColourId};
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum RuleProblem {
+    /// A bag that eventually holds itself, as the chain of colours from the
+    /// bag back around to itself
+    Cycle(Vec<String>),
+    /// A colour that is held by other bags but never has its own rule
+    UndefinedColour {
+        colour: String,
+        used_by: Vec<String>,
+    },
+    /// A colour with more than one rule
+    DuplicateDefinition { colour: String, count: usize },
+    /// A colour that neither holds nor is held by the colour we care about,
+    /// so has no effect on either answer
+    Unreachable(String),
+    /// A total that got too big for the number type counting it, at the
+    /// rule where `colour` holds `quantity` `holds` bags
+    Overflow {
+        colour: String,
+        holds: String,
+        quantity: usize,
+    },
+}
+
+impl std::fmt::Display for RuleProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuleProblem::Cycle(path) => write!(f, "cycle: {}", path.join(" -> ")),
+            RuleProblem::UndefinedColour { colour, used_by } if used_by.is_empty() => {
+                write!(f, "undefined colour: {}", colour)
+            }
+            RuleProblem::UndefinedColour { colour, used_by } => write!(
+                f,
+                "undefined colour: {} (used by {})",
+                colour,
+                used_by.join(", ")
+            ),
+            RuleProblem::DuplicateDefinition { colour, count } => {
+                write!(f, "{} is defined {} times", colour, count)
+            }
+            RuleProblem::Unreachable(colour) => write!(f, "unreachable colour: {}", colour),
+            RuleProblem::Overflow {
+                colour,
+                holds,
+                quantity,
+            } => write!(
+                f,
+                "total overflowed where {} holds {} {}",
+                colour, quantity, holds
+            ),
+        }
+    }
+}
+
+/// Check every rule in the graph, returning all of the problems found.
+/// `root` is the colour the puzzle asks about, used to find unreachable bags.
+pub fn validate(graph: &BagGraph, root: &str) -> Vec<RuleProblem> {
+    let mut problems = Vec::<RuleProblem>::new();
+    for id in 0..graph.len() {
+        if !graph.is_defined(id) {
+            problems.push(RuleProblem::UndefinedColour {
+                colour: String::from(graph.colour(id)),
+                used_by: graph.colours_for(
+                    &graph
+                        .parents(id)
+                        .iter()
+                        .map(|(parent, _)| *parent)
+                        .collect::<Vec<ColourId>>(),
+                ),
+            });
+        } else if graph.definition_count(id) > 1 {
+            problems.push(RuleProblem::DuplicateDefinition {
+                colour: String::from(graph.colour(id)),
+                count: graph.definition_count(id),
+            });
+        }
+    }
+    for cycle in find_cycles(graph) {
+        problems.push(RuleProblem::Cycle(graph.colours_for(&cycle)));
+    }
+    match graph.id(root) {
+        Some(root_id) => {
+            let mut reachable = vec![false; graph.len()];
+            reachable[root_id] = true;
+            for id in graph
+                .ancestors(root_id)
+                .into_iter()
+                .chain(graph.descendants(root_id))
+            {
+                reachable[id] = true;
+            }
+            for (id, reachable) in reachable.iter().enumerate() {
+                if !reachable {
+                    problems.push(RuleProblem::Unreachable(String::from(graph.colour(id))));
+                }
+            }
+        }
+        None => problems.push(RuleProblem::UndefinedColour {
+            colour: String::from(root),
+            used_by: vec![],
+        }),
+    }
+    problems
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum VisitState {
+    Unvisited,
+    OnPath,
+    Finished,
+}
+
+/// Depth first search over the whole graph, returning each cycle found as the
+/// path of colour ids from a bag back around to itself.
+pub fn find_cycles(graph: &BagGraph) -> Vec<Vec<ColourId>> {
+    let mut cycles = Vec::<Vec<ColourId>>::new();
+    let mut state = vec![VisitState::Unvisited; graph.len()];
+    for start in 0..graph.len() {
+        if state[start] != VisitState::Unvisited {
+            continue;
+        }
+        // Each entry is a bag on the current path and the next child to visit
+        let mut stack: Vec<(ColourId, usize)> = vec![(start, 0)];
+        state[start] = VisitState::OnPath;
+        while let Some((id, next_child)) = stack.last_mut() {
+            let id = *id;
+            match graph.children(id).get(*next_child) {
+                Some((child, _)) => {
+                    *next_child += 1;
+                    match state[*child] {
+                        VisitState::Unvisited => {
+                            state[*child] = VisitState::OnPath;
+                            stack.push((*child, 0));
+                        }
+                        VisitState::OnPath => {
+                            let start = stack.iter().position(|(bag, _)| bag == child).unwrap();
+                            let mut cycle: Vec<ColourId> =
+                                stack[start..].iter().map(|(bag, _)| *bag).collect();
+                            cycle.push(*child);
+                            cycles.push(cycle);
+                        }
+                        VisitState::Finished => (),
+                    }
+                }
+                None => {
+                    state[id] = VisitState::Finished;
+                    stack.pop();
+                }
+            }
+        }
+    }
+    cycles
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{bag_parser, Bag};
+
+    fn parse_graph(lines: &str) -> BagGraph {
+        let bags: Vec<Bag> = lines
+            .lines()
+            .map(bag_parser::parse)
+            .map(Result::unwrap)
+            .collect();
+        BagGraph::new(&bags)
+    }
+
+    #[test]
+    fn test_valid_rules() {
+        let graph = parse_graph(include_str!("../test_data_b.txt"));
+        assert_eq!(validate(&graph, "shiny gold"), []);
+    }
+
+    #[test]
+    fn test_unreachable() {
+        let graph = parse_graph(include_str!("../test_data_a.txt"));
+        assert_eq!(validate(&graph, "shiny gold"), []);
+        assert_eq!(
+            validate(&graph, "bright white"),
+            [RuleProblem::Unreachable(String::from("muted yellow"))]
+        );
+    }
+
+    #[test]
+    fn test_problems() {
+        let graph = parse_graph(
+            "red bags contain 1 blue bag.\n\
+             blue bags contain 2 green bags, 3 black bags.\n\
+             green bags contain 1 red bag.\n\
+             green bags contain no other bags.\n\
+             white bags contain no other bags.",
+        );
+        assert_eq!(
+            validate(&graph, "red"),
+            [
+                RuleProblem::DuplicateDefinition {
+                    colour: String::from("green"),
+                    count: 2
+                },
+                RuleProblem::UndefinedColour {
+                    colour: String::from("black"),
+                    used_by: vec![String::from("blue")]
+                },
+                RuleProblem::Cycle(vec![
+                    String::from("red"),
+                    String::from("blue"),
+                    String::from("green"),
+                    String::from("red")
+                ]),
+                RuleProblem::Unreachable(String::from("white")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_undefined_root() {
+        let graph = parse_graph(include_str!("../test_data_b.txt"));
+        assert_eq!(
+            validate(&graph, "tartan"),
+            [RuleProblem::UndefinedColour {
+                colour: String::from("tartan"),
+                used_by: vec![]
+            }]
+        );
+    }
+
+    #[test]
+    fn test_self_cycle() {
+        let graph = parse_graph("red bags contain 2 red bags.");
+        assert_eq!(find_cycles(&graph), [vec![0, 0]]);
+    }
+
+    #[test]
+    fn test_problem_display() {
+        let problem = RuleProblem::Cycle(vec![String::from("red"), String::from("red")]);
+        assert_eq!(problem.to_string(), "cycle: red -> red");
+    }
+}
@@ -0,0 +1,105 @@
+// Expense reports with exactly one pair and one triple adding up to 2020.
+// The other entries are all between 1011 and 2019, so any two of them add
+// up to more than 2020 and they can only make 2020 with the planted numbers,
+// which they're kept away from.
+use crate::{Generated, Rng};
+
+const TOTAL: i32 = 2020;
+
+// Whether the planted numbers only make 2020 as the pair and the triple
+fn only_planted(pair: &[i32; 2], triple: &[i32; 3]) -> bool {
+    let all: Vec<i32> = pair.iter().chain(triple.iter()).copied().collect();
+    let mut pairs = 0;
+    let mut triples = 0;
+    for i in 0..all.len() {
+        for j in i + 1..all.len() {
+            if all[i] == all[j] {
+                return false;
+            }
+            if all[i] + all[j] == TOTAL {
+                pairs += 1;
+            }
+            for k in j + 1..all.len() {
+                if all[i] + all[j] + all[k] == TOTAL {
+                    triples += 1;
+                }
+            }
+        }
+    }
+    pairs == 1 && triples == 1
+}
+
+/// `size` entries (at least 5), one a line. The answers are the products of
+/// the pair and the triple.
+pub fn generate(seed: u64, size: usize) -> Generated<i32, i32> {
+    let mut rng = Rng::new(seed);
+    let (pair, triple) = loop {
+        let a = rng.range(1, 1009) as i32;
+        let pair = [a, TOTAL - a];
+        let x = rng.range(1, 1000) as i32;
+        let y = rng.range(1, (TOTAL - x - 2) as u64 / 2) as i32;
+        let triple = [x, y, TOTAL - x - y];
+        if only_planted(&pair, &triple) {
+            break (pair, triple);
+        }
+    };
+    let planted: Vec<i32> = pair.iter().chain(triple.iter()).copied().collect();
+    let mut taken = vec![false; TOTAL as usize];
+    for (i, p) in planted.iter().enumerate() {
+        taken[*p as usize] = true;
+        taken[(TOTAL - p) as usize] = true;
+        for q in planted[i + 1..].iter() {
+            if p + q < TOTAL {
+                taken[(TOTAL - p - q) as usize] = true;
+            }
+        }
+    }
+    let free: Vec<i32> = (1011..TOTAL).filter(|n| !taken[*n as usize]).collect();
+    let mut numbers = planted;
+    // These can repeat, as there are only so many of them. Two 1011s still
+    // add up to more than 2020.
+    while numbers.len() < size.max(5) {
+        numbers.push(*rng.choose(&free));
+    }
+    rng.shuffle(&mut numbers);
+    Generated {
+        input: numbers.iter().map(|n| format!("{}\n", n)).collect(),
+        part_a: pair[0] * pair[1],
+        part_b: triple[0] * triple[1] * triple[2],
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_generate() {
+        for seed in 0..10 {
+            let generated = generate(seed, 60);
+            let numbers: Vec<i32> = generated
+                .input
+                .lines()
+                .map(|n| n.parse().unwrap())
+                .collect();
+            assert_eq!(numbers.len(), 60);
+            let mut pairs = vec![];
+            let mut triples = vec![];
+            for i in 0..numbers.len() {
+                for j in i + 1..numbers.len() {
+                    if numbers[i] + numbers[j] == TOTAL {
+                        pairs.push(numbers[i] * numbers[j]);
+                    }
+                    for k in j + 1..numbers.len() {
+                        if numbers[i] + numbers[j] + numbers[k] == TOTAL {
+                            triples.push(numbers[i] * numbers[j] * numbers[k]);
+                        }
+                    }
+                }
+            }
+            assert_eq!(pairs, [generated.part_a]);
+            assert_eq!(triples, [generated.part_b]);
+        }
+        assert_eq!(generate(7, 10), generate(7, 10));
+    }
+}
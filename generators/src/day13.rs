@@ -0,0 +1,110 @@
+// Bus schedules in the puzzle's two line format, with prime (so coprime)
+// bus ids. Part b's answer is planted: pick the time first, then put each
+// bus at a position in the list where it leaves that many minutes after it.
+use crate::{Generated, Rng};
+
+/// The bus ids to choose from
+fn primes_below(n: u64) -> Vec<u64> {
+    let mut primes: Vec<u64> = vec![];
+    for candidate in 2..n {
+        if primes
+            .iter()
+            .take_while(|p| *p * *p <= candidate)
+            .all(|p| candidate % p != 0)
+        {
+            primes.push(candidate);
+        }
+    }
+    primes
+}
+
+/// A schedule with `size` buses, or as many as fit before the product of
+/// their ids (how often part b's answer repeats) stops fitting in a u128.
+/// Part a is the earliest bus's id times the wait for it, part b the
+/// earliest time every bus leaves at its position in the list.
+pub fn generate(seed: u64, size: usize) -> Generated<u128, u128> {
+    let mut rng = Rng::new(seed);
+    let mut primes = primes_below(1000);
+    rng.shuffle(&mut primes);
+    let mut ids: Vec<u128> = vec![];
+    let mut repeat: u128 = 1;
+    for prime in primes.into_iter().take(size.max(1)) {
+        match repeat.checked_mul(prime as u128) {
+            Some(product) => {
+                repeat = product;
+                ids.push(prime as u128);
+            }
+            None => break,
+        }
+    }
+    let time = ((rng.next_u64() as u128) << 64 | rng.next_u64() as u128) % repeat;
+
+    // Bus `id` at position `i` needs `time + i` to be a multiple of `id`
+    let mut positions: Vec<Option<u128>> = vec![];
+    for id in ids.iter() {
+        let mut position = (id - time % id) % id + id * rng.below(3) as u128;
+        while positions
+            .get(position as usize)
+            .is_some_and(Option::is_some)
+        {
+            position += id;
+        }
+        if positions.len() <= position as usize {
+            positions.resize(position as usize + 1, None);
+        }
+        positions[position as usize] = Some(*id);
+    }
+
+    // Choose a start time with a single earliest bus, so part a has one answer
+    let (start, part_a) = loop {
+        let start = rng.range(1, 10_000_000) as u128;
+        let mut waits: Vec<(u128, u128)> =
+            ids.iter().map(|id| ((id - start % id) % id, *id)).collect();
+        waits.sort_unstable();
+        if waits.len() == 1 || waits[0].0 != waits[1].0 {
+            break (start, waits[0].0 * waits[0].1);
+        }
+    };
+    let list: Vec<String> = positions
+        .iter()
+        .map(|id| id.map_or(String::from("x"), |id| id.to_string()))
+        .collect();
+    Generated {
+        input: format!("{}\n{}\n", start, list.join(",")),
+        part_a,
+        part_b: time,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_primes() {
+        assert_eq!(primes_below(20), [2, 3, 5, 7, 11, 13, 17, 19]);
+        assert_eq!(primes_below(1000).len(), 168);
+    }
+
+    #[test]
+    fn test_generate() {
+        for seed in 0..20 {
+            let generated = generate(seed, 5);
+            let lines: Vec<&str> = generated.input.lines().collect();
+            let start: u128 = lines[0].parse().unwrap();
+            let mut waits = vec![];
+            for (i, id) in lines[1].split(',').enumerate() {
+                if let Ok(id) = id.parse::<u128>() {
+                    assert_eq!((generated.part_b + i as u128) % id, 0);
+                    waits.push(((id - start % id) % id, id));
+                }
+            }
+            assert_eq!(waits.len(), 5);
+            let (wait, id) = waits.iter().min().unwrap();
+            assert_eq!(generated.part_a, wait * id);
+        }
+        // 168 primes is far too many to multiply in a u128
+        let buses = generate(1, 168).input.matches(',').count();
+        assert!(buses > 10);
+    }
+}
@@ -0,0 +1,56 @@
+// Password lines like `1-3 a: abcde`. The letters come from a small alphabet
+// so the policy letter turns up often enough for both answers to be mixed.
+use crate::{Generated, Rng};
+
+const LETTERS: &[u8] = b"abcde";
+
+/// `size` password lines. The answers are how many are valid by each policy.
+pub fn generate(seed: u64, size: usize) -> Generated<usize, usize> {
+    let mut rng = Rng::new(seed);
+    let mut input = String::new();
+    let mut part_a = 0;
+    let mut part_b = 0;
+    for _ in 0..size {
+        let length = rng.range(2, 20) as usize;
+        let password: String = (0..length).map(|_| *rng.choose(LETTERS) as char).collect();
+        let letter = *rng.choose(LETTERS) as char;
+        // Both numbers are positions in the password for part b
+        let a = rng.range(1, length as u64 - 1) as usize;
+        let b = rng.range(a as u64 + 1, length as u64) as usize;
+        let count = password.chars().filter(|c| *c == letter).count();
+        if (a..=b).contains(&count) {
+            part_a += 1;
+        }
+        let at = |position: usize| password.as_bytes()[position - 1] as char == letter;
+        if at(a) != at(b) {
+            part_b += 1;
+        }
+        input += &format!("{}-{} {}: {}\n", a, b, letter, password);
+    }
+    Generated {
+        input,
+        part_a,
+        part_b,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_generate() {
+        let generated = generate(1, 200);
+        assert_eq!(generated.input.lines().count(), 200);
+        // Roughly a fair mix of valid and invalid
+        assert!((20..180).contains(&generated.part_a));
+        assert!((20..180).contains(&generated.part_b));
+        let generated = generate(2, 1);
+        assert_eq!(
+            generated.input.split(['-', ' ']).count(),
+            4,
+            "{:?} is not a password line",
+            generated.input
+        );
+    }
+}
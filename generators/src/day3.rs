@@ -0,0 +1,70 @@
+// Ski maps of `.` and `#`, which repeat to the right like the puzzle's.
+use crate::{Generated, Rng};
+
+/// The slopes day 3 part b multiplies together, as (right, down)
+pub const SLOPES: [(usize, usize); 5] = [(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)];
+
+fn trees_hit(map: &[Vec<bool>], right: usize, down: usize) -> usize {
+    (0..map.len())
+        .step_by(down)
+        .enumerate()
+        .filter(|(step, y)| map[*y][(step * right) % map[*y].len()])
+        .count()
+}
+
+/// A map `size` rows tall, about a quarter trees. Part a is the trees hit
+/// going right 3 and down 1, part b the product over all the `SLOPES`.
+/// Panics if part b is too big for a usize, which takes tens of thousands of rows.
+pub fn generate(seed: u64, size: usize) -> Generated<usize, usize> {
+    let mut rng = Rng::new(seed);
+    let width = rng.range(5, 31) as usize;
+    let map: Vec<Vec<bool>> = (0..size.max(1))
+        .map(|_| (0..width).map(|_| rng.one_in(4)).collect())
+        .collect();
+    let input = map
+        .iter()
+        .map(|row| {
+            let mut line: String = row
+                .iter()
+                .map(|tree| if *tree { '#' } else { '.' })
+                .collect();
+            line.push('\n');
+            line
+        })
+        .collect();
+    Generated {
+        input,
+        part_a: trees_hit(&map, 3, 1),
+        part_b: SLOPES
+            .iter()
+            .try_fold(1_usize, |product, (right, down)| {
+                product.checked_mul(trees_hit(&map, *right, *down))
+            })
+            .expect("Part b is too big for a usize"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_trees_hit() {
+        // The puzzle's example
+        let map: Vec<Vec<bool>> = include_str!("../../day3/test_data.txt")
+            .lines()
+            .map(|line| line.chars().map(|c| c == '#').collect())
+            .collect();
+        assert_eq!(trees_hit(&map, 3, 1), 7);
+        assert_eq!(trees_hit(&map, 1, 2), 2);
+    }
+
+    #[test]
+    fn test_generate() {
+        let generated = generate(5, 100);
+        let lines: Vec<&str> = generated.input.lines().collect();
+        assert_eq!(lines.len(), 100);
+        assert!(lines.iter().all(|line| line.len() == lines[0].len()));
+        assert!(generated.part_a > 0 && generated.part_a < 100);
+    }
+}
@@ -0,0 +1,147 @@
+// Passports with a chosen number of each kind of (in)validity, spread over
+// lines at random like the puzzle's, with an optional `cid` thrown in.
+use crate::{Generated, Rng};
+
+const FIELDS: [&str; 7] = ["byr", "iyr", "eyr", "hgt", "hcl", "ecl", "pid"];
+const EYE_COLOURS: [&str; 7] = ["amb", "blu", "brn", "gry", "grn", "hzl", "oth"];
+const HEX: &[u8] = b"0123456789abcdef";
+
+/// How many of each kind of passport to make
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PassportMix {
+    /// Missing at least one required field, so invalid for both parts
+    pub missing_field: usize,
+    /// Every field, but one with a bad value, so only valid for part a
+    pub invalid_value: usize,
+    /// Valid for both parts
+    pub valid: usize,
+}
+
+fn valid_value(rng: &mut Rng, field: &str) -> String {
+    match field {
+        "byr" => rng.range(1920, 2002).to_string(),
+        "iyr" => rng.range(2010, 2020).to_string(),
+        "eyr" => rng.range(2020, 2030).to_string(),
+        "hgt" if rng.one_in(2) => format!("{}cm", rng.range(150, 193)),
+        "hgt" => format!("{}in", rng.range(59, 76)),
+        "hcl" => {
+            let digits: String = (0..6).map(|_| *rng.choose(HEX) as char).collect();
+            format!("#{}", digits)
+        }
+        "ecl" => String::from(*rng.choose(&EYE_COLOURS)),
+        "pid" => format!("{:09}", rng.below(1_000_000_000)),
+        _ => format!("{}", rng.range(100, 999)),
+    }
+}
+
+fn invalid_value(rng: &mut Rng, field: &str) -> String {
+    let choices: &[&str] = match field {
+        "byr" => &["1919", "2003", "19a0", "0"],
+        "iyr" => &["2009", "2021", "20100"],
+        "eyr" => &["2019", "2031", "eyr"],
+        "hgt" => &["149cm", "194cm", "58in", "77in", "170", "60cm", "190in"],
+        "hcl" => &["123abc", "#12345g", "#1234", "#ABCDEF", "#1234567"],
+        "ecl" => &["wat", "blk", "BRN", "#123abc"],
+        _ => &["12345678", "1234567890", "12345678a"],
+    };
+    String::from(*rng.choose(choices))
+}
+
+fn passport(rng: &mut Rng, fields: &[(&str, String)]) -> String {
+    let mut ret = String::new();
+    for (i, (field, value)) in fields.iter().enumerate() {
+        if i > 0 {
+            ret.push(if rng.one_in(3) { '\n' } else { ' ' });
+        }
+        ret += &format!("{}:{}", field, value);
+    }
+    ret
+}
+
+/// The passports in `mix`, in a random order. The answers are how many are
+/// valid for each part.
+pub fn generate_mix(seed: u64, mix: PassportMix) -> Generated<usize, usize> {
+    let mut rng = Rng::new(seed);
+    let mut kinds: Vec<u8> = std::iter::repeat_n(0, mix.missing_field)
+        .chain(std::iter::repeat_n(1, mix.invalid_value))
+        .chain(std::iter::repeat_n(2, mix.valid))
+        .collect();
+    rng.shuffle(&mut kinds);
+    let mut passports = vec![];
+    for kind in kinds {
+        let mut fields: Vec<(&str, String)> = FIELDS
+            .iter()
+            .map(|field| (*field, valid_value(&mut rng, field)))
+            .collect();
+        match kind {
+            0 => {
+                let missing = rng.range(1, 3) as usize;
+                rng.shuffle(&mut fields);
+                fields.truncate(FIELDS.len() - missing);
+            }
+            1 => {
+                let bad = rng.below(FIELDS.len() as u64) as usize;
+                fields[bad].1 = invalid_value(&mut rng, fields[bad].0);
+            }
+            _ => (),
+        }
+        if rng.one_in(2) {
+            fields.push(("cid", valid_value(&mut rng, "cid")));
+        }
+        rng.shuffle(&mut fields);
+        passports.push(passport(&mut rng, &fields));
+    }
+    let mut input = passports.join("\n\n");
+    input.push('\n');
+    Generated {
+        input,
+        part_a: mix.invalid_value + mix.valid,
+        part_b: mix.valid,
+    }
+}
+
+/// `size` passports, with a random mix of validity
+pub fn generate(seed: u64, size: usize) -> Generated<usize, usize> {
+    let mut rng = Rng::new(seed);
+    let missing_field = rng.range(0, size as u64) as usize;
+    let invalid_value = rng.range(0, (size - missing_field) as u64) as usize;
+    generate_mix(
+        rng.next_u64(),
+        PassportMix {
+            missing_field,
+            invalid_value,
+            valid: size - missing_field - invalid_value,
+        },
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_generate_mix() {
+        let mix = PassportMix {
+            missing_field: 3,
+            invalid_value: 4,
+            valid: 5,
+        };
+        let generated = generate_mix(9, mix);
+        assert_eq!(generated.input.split("\n\n").count(), 12);
+        assert_eq!((generated.part_a, generated.part_b), (9, 5));
+        let full = generated
+            .input
+            .split("\n\n")
+            .filter(|passport| FIELDS.iter().all(|field| passport.contains(field)))
+            .count();
+        assert_eq!(full, 9);
+    }
+
+    #[test]
+    fn test_generate() {
+        let generated = generate(4, 30);
+        assert_eq!(generated.input.split("\n\n").count(), 30);
+        assert!(generated.part_b <= generated.part_a);
+        assert_eq!(generate(4, 0).input, "\n");
+    }
+}
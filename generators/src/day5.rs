@@ -0,0 +1,65 @@
+// Boarding passes for a run of seats with one missing in the middle.
+use crate::{Generated, Rng};
+
+/// Every seat id on the plane fits in 10 bits
+pub const SEATS: u32 = 1024;
+
+/// The pass for a seat id, e.g. `FBFBBFFRLR` for 357
+pub fn boarding_pass(seat: u32) -> String {
+    (0..10)
+        .map(|bit| {
+            let one = seat & (1 << (9 - bit)) != 0;
+            match (bit < 7, one) {
+                (true, true) => 'B',
+                (true, false) => 'F',
+                (false, true) => 'R',
+                (false, false) => 'L',
+            }
+        })
+        .collect()
+}
+
+/// `size` passes (between 2 and 1023) for consecutive seats, apart from one
+/// gap that isn't the first or last seat. Part a is the highest seat id,
+/// part b the missing one.
+pub fn generate(seed: u64, size: usize) -> Generated<u32, u32> {
+    let mut rng = Rng::new(seed);
+    let count = size.clamp(2, SEATS as usize - 1) as u32;
+    // The passes cover `count + 1` seats, including the gap
+    let first = rng.range(0, (SEATS - count - 1) as u64) as u32;
+    let last = first + count;
+    let gap = rng.range(first as u64 + 1, last as u64 - 1) as u32;
+    let mut seats: Vec<u32> = (first..=last).filter(|seat| *seat != gap).collect();
+    rng.shuffle(&mut seats);
+    Generated {
+        input: seats
+            .iter()
+            .map(|seat| format!("{}\n", boarding_pass(*seat)))
+            .collect(),
+        part_a: last,
+        part_b: gap,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_boarding_pass() {
+        assert_eq!(boarding_pass(357), "FBFBBFFRLR");
+        assert_eq!(boarding_pass(820), "BBFFBBFRLL");
+        assert_eq!(boarding_pass(0), "FFFFFFFLLL");
+    }
+
+    #[test]
+    fn test_generate() {
+        for size in [1, 2, 100, 1023, 5000] {
+            let generated = generate(size as u64, size);
+            let passes = generated.input.lines().count();
+            assert_eq!(passes, size.clamp(2, 1023));
+            assert!(generated.part_b < generated.part_a);
+            assert!(!generated.input.contains(&boarding_pass(generated.part_b)));
+        }
+    }
+}
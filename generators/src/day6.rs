@@ -0,0 +1,71 @@
+// Customs forms in groups. Each group has a few questions everyone answers,
+// so part b isn't always 0, on top of everyone's own random answers.
+use crate::{Generated, Rng};
+
+fn random_answers(rng: &mut Rng, max: u64) -> u32 {
+    (0..rng.range(0, max)).fold(0, |set, _| set | 1 << rng.below(26))
+}
+
+fn form(answers: u32) -> String {
+    (0..26)
+        .filter(|question| answers & (1 << question) != 0)
+        .map(|question| (b'a' + question as u8) as char)
+        .collect()
+}
+
+/// `size` groups of 1 to 5 people. The answers are the total of how many
+/// questions anyone in each group answered, and how many everyone did.
+pub fn generate(seed: u64, size: usize) -> Generated<usize, usize> {
+    let mut rng = Rng::new(seed);
+    let mut groups = vec![];
+    let mut part_a = 0;
+    let mut part_b = 0;
+    for _ in 0..size {
+        let common = random_answers(&mut rng, 3);
+        let mut any = 0;
+        let mut all = u32::MAX;
+        let mut forms = vec![];
+        for _ in 0..rng.range(1, 5) {
+            let mut answers = common | random_answers(&mut rng, 8);
+            // A blank form would split the group in two
+            if answers == 0 {
+                answers = 1 << rng.below(26);
+            }
+            any |= answers;
+            all &= answers;
+            forms.push(form(answers));
+        }
+        part_a += any.count_ones() as usize;
+        part_b += all.count_ones() as usize;
+        groups.push(forms.join("\n"));
+    }
+    let mut input = groups.join("\n\n");
+    input.push('\n');
+    Generated {
+        input,
+        part_a,
+        part_b,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_generate() {
+        let generated = generate(6, 50);
+        let groups: Vec<&str> = generated.input.split("\n\n").collect();
+        assert_eq!(groups.len(), 50);
+        assert!(groups.iter().all(|group| group
+            .lines()
+            .all(|form| !form.is_empty() && form.chars().all(|c| c.is_ascii_lowercase()))));
+        assert!(generated.part_b > 0 && generated.part_b < generated.part_a);
+    }
+
+    #[test]
+    fn test_form() {
+        assert_eq!(form(0b101), "ac");
+        assert_eq!(form(1 << 25), "z");
+    }
+}
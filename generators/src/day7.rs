@@ -0,0 +1,143 @@
+// Bag rules with no cycles: the colours are put in a random order and each
+// one only holds colours after it. Shiny gold goes near the end, so the bags
+// inside it stay few enough for part b to fit in a u128.
+use crate::{Generated, Rng};
+
+const ADJECTIVES: [&str; 16] = [
+    "light", "dark", "bright", "muted", "faded", "dotted", "vibrant", "pale", "dull", "posh",
+    "wavy", "plaid", "striped", "clear", "drab", "mirrored",
+];
+const COLOURS: [&str; 16] = [
+    "red", "orange", "white", "yellow", "olive", "plum", "blue", "black", "green", "tan", "teal",
+    "violet", "cyan", "beige", "crimson", "silver",
+];
+
+/// How many colours at most can be after (so maybe inside) shiny gold
+const INSIDE_GOLD: u64 = 24;
+
+/// A different colour for each `n`, made up of more words as `n` grows
+fn colour_name(n: usize) -> String {
+    let pairs = ADJECTIVES.len() * COLOURS.len();
+    let mut name = format!(
+        "{} {}",
+        ADJECTIVES[n % ADJECTIVES.len()],
+        COLOURS[n / ADJECTIVES.len() % COLOURS.len()]
+    );
+    // Past every pair add a made up word, starting with x so it's never "bag"
+    let mut extra = n / pairs;
+    if extra > 0 {
+        let mut word = String::from("x");
+        while extra > 0 {
+            word.push((b'a' + (extra % 26) as u8) as char);
+            extra /= 26;
+        }
+        name = format!("{} {}", word, name);
+    }
+    name
+}
+
+/// Rules for `size` colours (at least 1), one of them shiny gold, in a random
+/// order. Part a is how many colours can eventually hold shiny gold, part b
+/// how many bags are inside one.
+pub fn generate(seed: u64, size: usize) -> Generated<usize, u128> {
+    let mut rng = Rng::new(seed);
+    let size = size.max(1);
+    let mut names: Vec<String> = (0..size).map(colour_name).collect();
+    // Neither word is in the lists, so this doesn't clash with another colour
+    let gold = size - 1 - rng.below(INSIDE_GOLD.min(size as u64)) as usize;
+    names[gold] = String::from("shiny gold");
+
+    // (inner colour, quantity) for each colour, only ever holding later ones
+    let holds: Vec<Vec<(usize, u128)>> = (0..size)
+        .map(|i| {
+            let mut inner: Vec<usize> = vec![];
+            if i + 1 < size && !rng.one_in(4) {
+                for _ in 0..rng.range(1, 3) {
+                    let colour = rng.range(i as u64 + 1, size as u64 - 1) as usize;
+                    if !inner.contains(&colour) {
+                        inner.push(colour);
+                    }
+                }
+            }
+            inner
+                .into_iter()
+                .map(|colour| (colour, rng.range(1, 4) as u128))
+                .collect()
+        })
+        .collect();
+
+    // Part a, by marking everything that holds gold from the end backwards
+    let mut holds_gold = vec![false; size];
+    for i in (0..gold).rev() {
+        holds_gold[i] = holds[i]
+            .iter()
+            .any(|(colour, _)| *colour == gold || holds_gold[*colour]);
+    }
+    // Part b, totalling each colour's bags from the end backwards
+    let mut totals = vec![0_u128; size];
+    for i in (gold..size).rev() {
+        totals[i] = holds[i]
+            .iter()
+            .map(|(colour, quantity)| quantity * (1 + totals[*colour]))
+            .sum();
+    }
+
+    let mut rules: Vec<String> = holds
+        .iter()
+        .enumerate()
+        .map(|(i, inner)| {
+            let inner: Vec<String> = inner
+                .iter()
+                .map(|(colour, quantity)| {
+                    let plural = if *quantity == 1 { "" } else { "s" };
+                    format!("{} {} bag{}", quantity, names[*colour], plural)
+                })
+                .collect();
+            let contents = if inner.is_empty() {
+                String::from("no other bags")
+            } else {
+                inner.join(", ")
+            };
+            format!("{} bags contain {}.\n", names[i], contents)
+        })
+        .collect();
+    rng.shuffle(&mut rules);
+    Generated {
+        input: rules.concat(),
+        part_a: holds_gold.iter().filter(|holds| **holds).count(),
+        part_b: totals[gold],
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_colour_name() {
+        assert_eq!(colour_name(0), "light red");
+        assert_eq!(colour_name(17), "dark orange");
+        assert_eq!(colour_name(256), "xb light red");
+        let names: Vec<String> = (0..2000).map(colour_name).collect();
+        for (i, name) in names.iter().enumerate() {
+            assert!(!names[..i].contains(name), "{} is there twice", name);
+        }
+    }
+
+    #[test]
+    fn test_generate() {
+        let generated = generate(7, 300);
+        assert_eq!(generated.input.lines().count(), 300);
+        assert_eq!(
+            generated
+                .input
+                .lines()
+                .filter(|rule| rule.starts_with("shiny gold bags contain"))
+                .count(),
+            1
+        );
+        let generated = generate(7, 1);
+        assert_eq!(generated.input, "shiny gold bags contain no other bags.\n");
+        assert_eq!((generated.part_a, generated.part_b), (0, 0));
+    }
+}
@@ -0,0 +1,91 @@
+// Made up puzzle inputs of any size, along with their answers, for testing
+// the days on more than the examples. Everything comes from a seed, so a
+// failing input can be made again (`cargo run -- DAY SEED SIZE`).
+//
+// The answers are either planted (like day 1's pair and triple) or worked out
+// here the simple way, without sharing any code with the days themselves.
+pub mod day1;
+pub mod day13;
+pub mod day2;
+pub mod day3;
+pub mod day4;
+pub mod day5;
+pub mod day6;
+pub mod day7;
+
+/// A generated input and the answers the day should give for it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Generated<A, B> {
+    pub input: String,
+    pub part_a: A,
+    pub part_b: B,
+}
+
+/// A small seeded random number generator (SplitMix64). Not for anything
+/// that matters, but the same seed gives the same numbers on every machine
+/// and Rust version, which is what we want for reproducing inputs.
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number from 0 up to but not including `n`. Panics if `n` is 0.
+    pub fn below(&mut self, n: u64) -> u64 {
+        assert!(n > 0, "Nothing is below 0");
+        self.next_u64() % n
+    }
+
+    /// A number in `low..=high`
+    pub fn range(&mut self, low: u64, high: u64) -> u64 {
+        low + self.below(high - low + 1)
+    }
+
+    /// True one time in `n`
+    pub fn one_in(&mut self, n: u64) -> bool {
+        self.below(n) == 0
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len() as u64) as usize]
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i as u64 + 1) as usize);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rng() {
+        // The SplitMix64 reference output for seed 1234567
+        let mut rng = Rng::new(1234567);
+        assert_eq!(rng.next_u64(), 6457827717110365317);
+        assert_eq!(rng.next_u64(), 3203168211198807973);
+
+        let mut rng = Rng::new(3);
+        for _ in 0..100 {
+            assert!((5..=7).contains(&rng.range(5, 7)));
+        }
+        let mut items: Vec<u32> = (0..20).collect();
+        rng.shuffle(&mut items);
+        assert_ne!(items, (0..20).collect::<Vec<u32>>());
+        items.sort_unstable();
+        assert_eq!(items, (0..20).collect::<Vec<u32>>());
+    }
+}
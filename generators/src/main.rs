@@ -0,0 +1,40 @@
+use generators::Generated;
+
+fn show<A: std::fmt::Display, B: std::fmt::Display>(generated: Generated<A, B>) {
+    print!("{}", generated.input);
+    eprintln!("Part a: {}", generated.part_a);
+    eprintln!("Part b: {}", generated.part_b);
+}
+
+fn main() {
+    // Usage: generators DAY SEED SIZE
+    // Writes the input to stdout and its answers to stderr, e.g.
+    //   cargo run -- 13 42 20 > schedule.txt
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let number = |i: usize, name: &str| -> u64 {
+        args.get(i)
+            .and_then(|arg| arg.parse().ok())
+            .unwrap_or_else(|| {
+                panic!(
+                    "Usage: generators DAY SEED SIZE, {} should be a number",
+                    name
+                )
+            })
+    };
+    let (day, seed, size) = (
+        number(0, "DAY"),
+        number(1, "SEED"),
+        number(2, "SIZE") as usize,
+    );
+    match day {
+        1 => show(generators::day1::generate(seed, size)),
+        2 => show(generators::day2::generate(seed, size)),
+        3 => show(generators::day3::generate(seed, size)),
+        4 => show(generators::day4::generate(seed, size)),
+        5 => show(generators::day5::generate(seed, size)),
+        6 => show(generators::day6::generate(seed, size)),
+        7 => show(generators::day7::generate(seed, size)),
+        13 => show(generators::day13::generate(seed, size)),
+        _ => panic!("There's no generator for day {}", day),
+    }
+}
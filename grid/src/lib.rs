@@ -0,0 +1,407 @@
+// A rectangular grid of cells, pulled out of day 3's ski map so the next
+// grid puzzle doesn't have to start from scratch.
+mod point;
+
+pub use point::{Connectivity, Point};
+
+/// What happens to a coordinate that falls off one side of the grid
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Edge {
+    /// There's nothing there
+    None,
+    /// The grid repeats endlessly, like the slope in day 3
+    Wrap,
+    /// Anything past the edge is the cell on the edge
+    Clamp,
+}
+
+impl Edge {
+    fn apply(self, position: isize, size: usize) -> Option<usize> {
+        let size = size as isize;
+        if size == 0 {
+            return None;
+        }
+        match self {
+            Edge::None if (0..size).contains(&position) => Some(position as usize),
+            Edge::None => None,
+            Edge::Wrap => Some(position.rem_euclid(size) as usize),
+            Edge::Clamp => Some(position.clamp(0, size - 1) as usize),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum GridParseError<E> {
+    /// There were no lines to parse
+    Empty,
+    /// A line wasn't as long as the first one. Lines count from 1.
+    RaggedLine {
+        line: usize,
+        width: usize,
+        expected: usize,
+    },
+    /// The cell function rejected a character. Lines and columns count from 1.
+    Cell {
+        line: usize,
+        column: usize,
+        error: E,
+    },
+}
+
+impl<E: std::fmt::Display> std::fmt::Display for GridParseError<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GridParseError::Empty => write!(f, "No lines in the grid"),
+            GridParseError::RaggedLine {
+                line,
+                width,
+                expected,
+            } => write!(
+                f,
+                "Line {} is {} wide, but the first line is {} wide",
+                line, width, expected
+            ),
+            GridParseError::Cell {
+                line,
+                column,
+                error,
+            } => write!(f, "Line {}, column {}: {}", line, column, error),
+        }
+    }
+}
+
+impl<E: std::fmt::Debug + std::fmt::Display> std::error::Error for GridParseError<E> {}
+
+/// Cells stored row by row, with an edge policy for each axis
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+    x_edge: Edge,
+    y_edge: Edge,
+}
+
+impl<T> Grid<T> {
+    /// A grid from its rows. Panics if the rows aren't all the same length.
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Grid<T> {
+        let height = rows.len();
+        let width = rows.first().map_or(0, Vec::len);
+        if rows.iter().any(|row| row.len() != width) {
+            panic!("Not all rows are the same width");
+        }
+        Grid {
+            width,
+            height,
+            cells: rows.into_iter().flatten().collect(),
+            x_edge: Edge::None,
+            y_edge: Edge::None,
+        }
+    }
+
+    /// Parse one cell per character, with a line per row.
+    /// Trailing whitespace on each line is ignored.
+    pub fn parse<E, F>(text: &str, mut cell: F) -> Result<Grid<T>, GridParseError<E>>
+    where
+        F: FnMut(char) -> Result<T, E>,
+    {
+        let mut rows = vec![];
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim_end();
+            let mut row = vec![];
+            for (j, c) in line.chars().enumerate() {
+                row.push(cell(c).map_err(|error| GridParseError::Cell {
+                    line: i + 1,
+                    column: j + 1,
+                    error,
+                })?);
+            }
+            if let Some(first) = rows.first().map(Vec::len) {
+                if row.len() != first {
+                    return Err(GridParseError::RaggedLine {
+                        line: i + 1,
+                        width: row.len(),
+                        expected: first,
+                    });
+                }
+            }
+            rows.push(row);
+        }
+        if rows.is_empty() {
+            return Err(GridParseError::Empty);
+        }
+        Ok(Grid::from_rows(rows))
+    }
+
+    /// The same grid with different edge policies for x and y
+    pub fn with_edges(self, x_edge: Edge, y_edge: Edge) -> Grid<T> {
+        Grid {
+            x_edge,
+            y_edge,
+            ..self
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Where `point` ends up after the edge policies, if anywhere
+    pub fn resolve(&self, point: Point) -> Option<Point> {
+        Some(Point::new(
+            self.x_edge.apply(point.x, self.width)? as isize,
+            self.y_edge.apply(point.y, self.height)? as isize,
+        ))
+    }
+
+    fn index(&self, point: Point) -> Option<usize> {
+        let point = self.resolve(point)?;
+        Some(point.y as usize * self.width + point.x as usize)
+    }
+
+    pub fn get(&self, point: Point) -> Option<&T> {
+        self.index(point).map(|i| &self.cells[i])
+    }
+
+    pub fn get_mut(&mut self, point: Point) -> Option<&mut T> {
+        self.index(point).map(move |i| &mut self.cells[i])
+    }
+
+    /// Every point on the grid, row by row
+    pub fn points(&self) -> impl Iterator<Item = Point> {
+        let width = self.width;
+        (0..self.height)
+            .flat_map(move |y| (0..width).map(move |x| Point::new(x as isize, y as isize)))
+    }
+
+    /// The neighbours of `point` that are on the grid after the edge
+    /// policies, with where they ended up. Clamping and wrapping on a small
+    /// grid can give the same cell more than once, or `point` itself.
+    pub fn neighbours(
+        &self,
+        point: Point,
+        connectivity: Connectivity,
+    ) -> impl Iterator<Item = (Point, &T)> + '_ {
+        connectivity.offsets().iter().filter_map(move |offset| {
+            let neighbour = self.resolve(point + *offset)?;
+            Some((neighbour, self.get(neighbour)?))
+        })
+    }
+
+    /// The cells in row `y`, left to right. Panics if `y` is off the grid.
+    pub fn row(&self, y: usize) -> impl Iterator<Item = &T> + '_ {
+        self.cells[y * self.width..(y + 1) * self.width].iter()
+    }
+
+    /// The cells in column `x`, top to bottom. Panics if `x` is off the grid.
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> + '_ {
+        assert!(x < self.width, "Column {} is off the grid", x);
+        self.cells.iter().skip(x).step_by(self.width.max(1))
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> + '_ {
+        self.cells.chunks(self.width.max(1))
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T> + '_> + '_ {
+        (0..self.width).map(move |x| self.column(x))
+    }
+
+    /// Draw the grid with a character per cell and a line per row
+    pub fn render<F: Fn(&T) -> char>(&self, cell: F) -> String {
+        let mut ret = String::new();
+        for row in self.rows() {
+            ret.extend(row.iter().map(&cell));
+            ret.push('\n');
+        }
+        ret
+    }
+}
+
+impl<T: Clone> Grid<T> {
+    /// A `width` by `height` grid filled with `value`
+    pub fn new(width: usize, height: usize, value: T) -> Grid<T> {
+        Grid {
+            width,
+            height,
+            cells: vec![value; width * height],
+            x_edge: Edge::None,
+            y_edge: Edge::None,
+        }
+    }
+
+    // Build a grid of the given size where each cell is copied from `from`
+    fn remap<F: Fn(usize, usize) -> Point>(&self, width: usize, height: usize, from: F) -> Grid<T> {
+        let mut cells = Vec::with_capacity(self.cells.len());
+        for y in 0..height {
+            for x in 0..width {
+                cells.push(self.get(from(x, y)).unwrap().clone());
+            }
+        }
+        Grid {
+            width,
+            height,
+            cells,
+            x_edge: self.y_edge,
+            y_edge: self.x_edge,
+        }
+    }
+
+    /// Flip over the top left to bottom right diagonal, so rows become columns
+    pub fn transpose(&self) -> Grid<T> {
+        self.remap(self.height, self.width, |x, y| {
+            Point::new(y as isize, x as isize)
+        })
+    }
+
+    /// Turn a quarter turn clockwise, so the first column becomes the first row backwards
+    pub fn rotate_clockwise(&self) -> Grid<T> {
+        let height = self.height;
+        self.remap(self.height, self.width, move |x, y| {
+            Point::new(y as isize, (height - 1 - x) as isize)
+        })
+    }
+
+    /// Turn a quarter turn counter clockwise, so the last column becomes the first row
+    pub fn rotate_counter_clockwise(&self) -> Grid<T> {
+        let width = self.width;
+        self.remap(self.height, self.width, move |x, y| {
+            Point::new((width - 1 - y) as isize, x as isize)
+        })
+    }
+}
+
+impl std::fmt::Display for Grid<char> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.render(|c| *c))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn example() -> Grid<char> {
+        Grid::parse("abc\ndef\n", Ok::<char, ()>).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        let grid = example();
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid.get(Point::new(2, 1)), Some(&'f'));
+        assert_eq!(grid.to_string(), "abc\ndef\n");
+
+        let trees = Grid::parse(".#\n#.", |c| match c {
+            '#' => Ok(true),
+            '.' => Ok(false),
+            other => Err(other),
+        })
+        .unwrap();
+        assert_eq!(trees.render(|t| if *t { '#' } else { '.' }), ".#\n#.\n");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(Grid::parse("", Ok::<char, ()>), Err(GridParseError::Empty));
+        assert_eq!(
+            Grid::parse("ab\nabc", Ok::<char, ()>),
+            Err(GridParseError::RaggedLine {
+                line: 2,
+                width: 3,
+                expected: 2
+            })
+        );
+        let digits = Grid::parse("12\n3x", |c| c.to_digit(10).ok_or(c));
+        assert_eq!(
+            digits,
+            Err(GridParseError::Cell {
+                line: 2,
+                column: 2,
+                error: 'x'
+            })
+        );
+        assert_eq!(digits.unwrap_err().to_string(), "Line 2, column 2: x");
+    }
+
+    #[test]
+    fn test_edges() {
+        let grid = example();
+        assert_eq!(grid.get(Point::new(-1, 0)), None);
+        assert_eq!(grid.get(Point::new(0, 2)), None);
+
+        let grid = example().with_edges(Edge::Wrap, Edge::None);
+        assert_eq!(grid.get(Point::new(-1, 0)), Some(&'c'));
+        assert_eq!(grid.get(Point::new(7, 1)), Some(&'e'));
+        assert_eq!(grid.get(Point::new(0, -1)), None);
+
+        let grid = example().with_edges(Edge::Clamp, Edge::Wrap);
+        assert_eq!(grid.get(Point::new(-5, 3)), Some(&'d'));
+        assert_eq!(grid.get(Point::new(9, -2)), Some(&'c'));
+    }
+
+    #[test]
+    fn test_get_mut() {
+        let mut grid = Grid::new(2, 2, 0);
+        *grid.get_mut(Point::new(1, 0)).unwrap() += 3;
+        assert_eq!(grid.rows().collect::<Vec<&[i32]>>(), [[0, 3], [0, 0]]);
+        assert_eq!(grid.get_mut(Point::new(2, 0)), None);
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let grid = example();
+        let around = |grid: &Grid<char>, point, connectivity| {
+            grid.neighbours(point, connectivity)
+                .map(|(_, c)| *c)
+                .collect::<String>()
+        };
+        assert_eq!(around(&grid, Point::new(0, 0), Connectivity::Four), "bd");
+        assert_eq!(
+            around(&grid, Point::new(1, 0), Connectivity::Eight),
+            "cfeda"
+        );
+        let grid = grid.with_edges(Edge::Wrap, Edge::Wrap);
+        assert_eq!(around(&grid, Point::new(0, 0), Connectivity::Four), "dbdc");
+        assert_eq!(
+            grid.neighbours(Point::new(0, 0), Connectivity::Eight)
+                .next(),
+            Some((Point::new(0, 1), &'d'))
+        );
+    }
+
+    #[test]
+    fn test_rows_and_columns() {
+        let grid = example();
+        assert_eq!(grid.row(1).collect::<String>(), "def");
+        assert_eq!(grid.column(2).collect::<String>(), "cf");
+        assert_eq!(
+            grid.columns()
+                .map(|column| column.collect::<String>())
+                .collect::<Vec<String>>(),
+            ["ad", "be", "cf"]
+        );
+        assert_eq!(grid.points().count(), 6);
+        assert_eq!(grid.points().last(), Some(Point::new(2, 1)));
+    }
+
+    #[test]
+    fn test_transform() {
+        let grid = example();
+        assert_eq!(grid.transpose().to_string(), "ad\nbe\ncf\n");
+        assert_eq!(grid.rotate_clockwise().to_string(), "da\neb\nfc\n");
+        assert_eq!(grid.rotate_counter_clockwise().to_string(), "cf\nbe\nad\n");
+        assert_eq!(grid.transpose().transpose(), grid);
+        let mut turned = grid.clone();
+        for _ in 0..4 {
+            turned = turned.rotate_clockwise();
+        }
+        assert_eq!(turned, grid);
+        let grid = grid.with_edges(Edge::Wrap, Edge::None);
+        assert_eq!(grid.transpose().get(Point::new(0, 5)), Some(&'c'));
+    }
+}
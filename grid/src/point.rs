@@ -0,0 +1,97 @@
+// Coordinates on a grid. Signed, so positions can step off the edge and
+// then be wrapped or clamped back on by the grid's edge policy.
+
+/// `x` goes right along a row, `y` goes down, with (0, 0) the top left
+#[derive(std::hash::Hash, PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub struct Point {
+    pub x: isize,
+    pub y: isize,
+}
+
+impl Point {
+    pub const fn new(x: isize, y: isize) -> Point {
+        Point { x, y }
+    }
+}
+
+impl std::ops::Add for Point {
+    type Output = Point;
+
+    fn add(self, other: Point) -> Point {
+        Point::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl std::ops::Sub for Point {
+    type Output = Point;
+
+    fn sub(self, other: Point) -> Point {
+        Point::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl std::ops::AddAssign for Point {
+    fn add_assign(&mut self, other: Point) {
+        *self = *self + other;
+    }
+}
+
+/// Which cells count as next to each other
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Connectivity {
+    /// Up, down, left and right
+    Four,
+    /// Four, plus the diagonals
+    Eight,
+}
+
+const FOUR: [Point; 4] = [
+    Point::new(0, -1),
+    Point::new(1, 0),
+    Point::new(0, 1),
+    Point::new(-1, 0),
+];
+
+const EIGHT: [Point; 8] = [
+    Point::new(0, -1),
+    Point::new(1, -1),
+    Point::new(1, 0),
+    Point::new(1, 1),
+    Point::new(0, 1),
+    Point::new(-1, 1),
+    Point::new(-1, 0),
+    Point::new(-1, -1),
+];
+
+impl Connectivity {
+    /// The steps to each neighbour, clockwise from straight up
+    pub fn offsets(self) -> &'static [Point] {
+        match self {
+            Connectivity::Four => &FOUR,
+            Connectivity::Eight => &EIGHT,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_arithmetic() {
+        let mut point = Point::new(1, 2) + Point::new(3, -4);
+        assert_eq!(point, Point::new(4, -2));
+        point += Point::new(1, 1);
+        assert_eq!(point - Point::new(5, -1), Point::default());
+    }
+
+    #[test]
+    fn test_offsets() {
+        assert_eq!(Connectivity::Four.offsets().len(), 4);
+        assert_eq!(Connectivity::Eight.offsets().len(), 8);
+        assert!(Connectivity::Four
+            .offsets()
+            .iter()
+            .all(|offset| Connectivity::Eight.offsets().contains(offset)));
+    }
+}
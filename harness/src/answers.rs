@@ -0,0 +1,286 @@
+// Known answers for real inputs, so a refactor can't quietly change them.
+// They live in `answers.toml` at the top of the repo, a table per day and
+// input (by a hash of its text) with an entry per part:
+//
+// [day1.750433d7940c1419]
+// a = "1005459"
+// b = "92643264"
+//
+// Answers are strings so every day's types fit, compared by their `Display`.
+use std::path::{Path, PathBuf};
+
+/// The answers file, next to the day directories
+pub const ANSWERS_FILE: &str = "answers.toml";
+
+pub fn answers_path(day_dir: &str) -> PathBuf {
+    Path::new(day_dir).join("..").join(ANSWERS_FILE)
+}
+
+/// A hash of an input's text, the same on every machine and Rust version
+/// (unlike `DefaultHasher`). 64 bit FNV-1a, in hex.
+pub fn input_hash(input: &str) -> String {
+    let hash = input.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    });
+    format!("{:016x}", hash)
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Verdict {
+    Correct,
+    Incorrect {
+        expected: String,
+    },
+    /// There's no answer saved for this input and part
+    Unknown,
+}
+
+#[derive(Debug)]
+pub enum AnswersError {
+    Io(std::io::Error),
+    Toml(toml::de::Error),
+    /// Something in the file that isn't a table of days, inputs and parts
+    Layout(String),
+}
+
+impl std::fmt::Display for AnswersError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnswersError::Io(err) => write!(f, "{}", err),
+            AnswersError::Toml(err) => write!(f, "{}", err),
+            AnswersError::Layout(key) => {
+                write!(f, "{} should be a table of parts and answers", key)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AnswersError {}
+
+/// Every saved answer, by day, input hash and part
+#[derive(Debug, PartialEq, Default)]
+pub struct Answers {
+    table: toml::Table,
+}
+
+impl Answers {
+    pub fn parse(text: &str) -> Result<Answers, AnswersError> {
+        let table: toml::Table = text.parse().map_err(AnswersError::Toml)?;
+        for (day, inputs) in table.iter() {
+            let inputs = inputs
+                .as_table()
+                .ok_or_else(|| AnswersError::Layout(day.clone()))?;
+            for (hash, parts) in inputs.iter() {
+                let key = format!("{}.{}", day, hash);
+                let parts = parts
+                    .as_table()
+                    .ok_or_else(|| AnswersError::Layout(key.clone()))?;
+                if parts.values().any(|answer| !answer.is_str()) {
+                    return Err(AnswersError::Layout(key));
+                }
+            }
+        }
+        Ok(Answers { table })
+    }
+
+    /// The saved answers, or none at all if there's no file yet
+    pub fn load(path: &Path) -> Result<Answers, AnswersError> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => Answers::parse(&text),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Answers::default()),
+            Err(err) => Err(AnswersError::Io(err)),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        std::fs::write(path, self.to_string())
+    }
+
+    pub fn get(&self, day: &str, hash: &str, part: &str) -> Option<&str> {
+        self.table.get(day)?.get(hash)?.get(part)?.as_str()
+    }
+
+    pub fn check(&self, day: &str, hash: &str, part: &str, answer: &str) -> Verdict {
+        match self.get(day, hash, part) {
+            Some(expected) if expected == answer => Verdict::Correct,
+            Some(expected) => Verdict::Incorrect {
+                expected: String::from(expected),
+            },
+            None => Verdict::Unknown,
+        }
+    }
+
+    pub fn record(&mut self, day: &str, hash: &str, part: &str, answer: &str) {
+        fn table(value: &mut toml::Value) -> &mut toml::Table {
+            match value {
+                toml::Value::Table(table) => table,
+                _ => unreachable!("Checked when the answers were parsed"),
+            }
+        }
+        let inputs = table(
+            self.table
+                .entry(day)
+                .or_insert_with(|| toml::Value::Table(toml::Table::new())),
+        );
+        let parts = table(
+            inputs
+                .entry(hash)
+                .or_insert_with(|| toml::Value::Table(toml::Table::new())),
+        );
+        parts.insert(String::from(part), toml::Value::from(answer));
+    }
+}
+
+impl std::fmt::Display for Answers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.table)
+    }
+}
+
+/// A runner's answers for one input, checked against the saved ones as they
+/// come in. With `record` set, `finish` saves them as the right answers.
+#[derive(Debug)]
+pub struct AnswerCheck {
+    path: PathBuf,
+    answers: Answers,
+    day: String,
+    hash: String,
+    record: bool,
+    incorrect: usize,
+    recorded: usize,
+}
+
+impl AnswerCheck {
+    /// Checks for `day`'s answers to `input`, against the answers file that
+    /// sits next to `day_dir`. An unreadable answers file is reported and
+    /// treated as empty, so the day still runs.
+    pub fn new(day_dir: &str, day: &str, input: &str, record: bool) -> AnswerCheck {
+        let path = answers_path(day_dir);
+        let answers = Answers::load(&path).unwrap_or_else(|err| {
+            println!("Ignoring the unreadable {:?}: {}", path, err);
+            Answers::default()
+        });
+        AnswerCheck {
+            path,
+            answers,
+            day: String::from(day),
+            hash: input_hash(input),
+            record,
+            incorrect: 0,
+            recorded: 0,
+        }
+    }
+
+    /// Check (or with `record`, save) one part's answer and say how it went
+    pub fn check<A: std::fmt::Display>(&mut self, part: &str, answer: A) -> Verdict {
+        let answer = answer.to_string();
+        let verdict = self.answers.check(&self.day, &self.hash, part, &answer);
+        match (&verdict, self.record) {
+            (Verdict::Correct, _) => println!("Part {} answer is correct", part),
+            (Verdict::Incorrect { expected }, false) => {
+                self.incorrect += 1;
+                println!(
+                    "Part {} answer is INCORRECT, {} has {}",
+                    part, ANSWERS_FILE, expected
+                );
+            }
+            (Verdict::Unknown, false) => println!(
+                "Part {} answer is unknown, run with --record once it's verified",
+                part
+            ),
+            (verdict, true) => {
+                if let Verdict::Incorrect { expected } = verdict {
+                    println!("Part {} answer replaces {}", part, expected);
+                }
+                self.answers.record(&self.day, &self.hash, part, &answer);
+                self.recorded += 1;
+            }
+        }
+        verdict
+    }
+
+    /// Save any recorded answers, then exit with a failure if any answer
+    /// didn't match, so scripts notice
+    pub fn finish(&self) {
+        if self.recorded > 0 {
+            match self.answers.save(&self.path) {
+                Ok(()) => println!("Recorded {} answers in {:?}", self.recorded, self.path),
+                Err(err) => println!("Could not save the answers {:?}: {}", self.path, err),
+            }
+        }
+        if self.incorrect > 0 {
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_input_hash() {
+        // The published FNV-1a test values
+        assert_eq!(input_hash(""), "cbf29ce484222325");
+        assert_eq!(input_hash("a"), "af63dc4c8601ec8c");
+        assert_ne!(input_hash("1\n2\n"), input_hash("1\n2"));
+    }
+
+    #[test]
+    fn test_check_and_record() {
+        let mut answers = Answers::parse("[day1.abc]\na = \"514579\"\n").unwrap();
+        assert_eq!(
+            answers.check("day1", "abc", "a", "514579"),
+            Verdict::Correct
+        );
+        assert_eq!(
+            answers.check("day1", "abc", "a", "1"),
+            Verdict::Incorrect {
+                expected: String::from("514579")
+            }
+        );
+        assert_eq!(answers.check("day1", "abc", "b", "1"), Verdict::Unknown);
+        assert_eq!(answers.check("day2", "abc", "a", "1"), Verdict::Unknown);
+        answers.record("day1", "abc", "b", "241861950");
+        answers.record("day2", "def", "a", "2");
+        assert_eq!(answers.get("day1", "abc", "b"), Some("241861950"));
+        assert_eq!(Answers::parse(&answers.to_string()).unwrap(), answers);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(matches!(
+            Answers::parse("[day1"),
+            Err(AnswersError::Toml(_))
+        ));
+        for (text, key) in [
+            ("day1 = 3", "day1"),
+            ("[day1]\nabc = 3", "day1.abc"),
+            ("[day1.abc]\na = 3", "day1.abc"),
+        ] {
+            match Answers::parse(text) {
+                Err(AnswersError::Layout(found)) => assert_eq!(found, key),
+                other => panic!("Expected {:?} to fail, got {:?}", text, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_answer_check() {
+        let dir = std::env::temp_dir().join(format!("answers_test_{}", std::process::id()));
+        let day_dir = dir.join("day1");
+        std::fs::create_dir_all(&day_dir).unwrap();
+        let day_dir = day_dir.to_str().unwrap();
+
+        let mut check = AnswerCheck::new(day_dir, "day1", "1721\n979", true);
+        assert_eq!(check.check("a", 514579), Verdict::Unknown);
+        check.finish();
+        let mut check = AnswerCheck::new(day_dir, "day1", "1721\n979", false);
+        assert_eq!(check.check("a", 514579), Verdict::Correct);
+        assert_eq!(check.check("b", 1), Verdict::Unknown);
+        let mut check = AnswerCheck::new(day_dir, "day1", "other input", false);
+        assert_eq!(check.check("a", 514579), Verdict::Unknown);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
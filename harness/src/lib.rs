@@ -0,0 +1,408 @@
+// Timing for the days' runners. Each day wraps parsing and its parts in
+// `Timings::time`, which does nothing special unless the runner was asked to
+// time (`--time`, one run of each) or benchmark (`--bench`, the median of
+// several runs, compared against the last benchmark saved to disk).
+// `answers` checks the answers they give against the known ones.
+pub mod answers;
+
+pub use answers::AnswerCheck;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// How many runs `--bench` takes the median of
+pub const BENCH_SAMPLES: usize = 20;
+
+/// How much slower than the baseline (as a fraction) counts as a regression
+pub const REGRESSION_THRESHOLD: f64 = 0.1;
+
+/// Anything faster than this is too quick to call a regression, as the
+/// timing noise is bigger than the time
+pub const REGRESSION_MINIMUM: Duration = Duration::from_micros(10);
+
+/// The file next to each day's Cargo.toml that holds its last benchmark
+pub const BASELINE_FILE: &str = "bench_baseline.txt";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    /// Just run everything once
+    Off,
+    /// Run everything once and print how long each step took
+    Time,
+    /// Run each step this many times and compare the medians to the baseline
+    Bench(usize),
+}
+
+impl Mode {
+    /// The mode for a runner argument, if it's one of ours
+    pub fn from_arg(arg: &str) -> Option<Mode> {
+        match arg {
+            "--time" => Some(Mode::Time),
+            "--bench" => Some(Mode::Bench(BENCH_SAMPLES)),
+            _ => None,
+        }
+    }
+
+    fn samples(self) -> usize {
+        match self {
+            Mode::Bench(samples) => samples.max(1),
+            _ => 1,
+        }
+    }
+}
+
+/// The arguments every runner understands
+#[derive(Debug, PartialEq)]
+pub struct Args {
+    pub mode: Mode,
+    /// Save the answers as the right ones (`--record`)
+    pub record: bool,
+}
+
+impl Args {
+    /// The arguments for a runner that takes no others
+    pub fn parse<I: Iterator<Item = String>>(args: I) -> Args {
+        let mut ret = Args {
+            mode: Mode::Off,
+            record: false,
+        };
+        for arg in args {
+            match (&arg[..], Mode::from_arg(&arg)) {
+                (_, Some(mode)) => ret.mode = mode,
+                ("--record", _) => ret.record = true,
+                _ => panic!("Unknown argument {}", arg),
+            }
+        }
+        ret
+    }
+}
+
+/// How long one step took, over all its runs
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timing {
+    pub name: String,
+    pub median: Duration,
+    pub min: Duration,
+    pub max: Duration,
+}
+
+#[derive(Debug)]
+pub struct Timings {
+    mode: Mode,
+    timings: Vec<Timing>,
+}
+
+impl Timings {
+    pub fn new(mode: Mode) -> Timings {
+        Timings {
+            mode,
+            timings: vec![],
+        }
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    pub fn timings(&self) -> &[Timing] {
+        &self.timings
+    }
+
+    /// Run `step` (as many times as the mode says) and keep how long it took.
+    /// Gives back the result of the last run.
+    pub fn time<T, F: FnMut() -> T>(&mut self, name: &str, mut step: F) -> T {
+        let mut durations = Vec::with_capacity(self.mode.samples());
+        let mut result = None;
+        for _ in 0..self.mode.samples() {
+            // Drop the previous result outside the timing
+            drop(result.take());
+            let start = Instant::now();
+            result = Some(std::hint::black_box(step()));
+            durations.push(start.elapsed());
+        }
+        durations.sort();
+        self.timings.push(Timing {
+            name: String::from(name),
+            median: durations[durations.len() / 2],
+            min: durations[0],
+            max: durations[durations.len() - 1],
+        });
+        result.unwrap()
+    }
+
+    /// Print the timings for `--time`, or for `--bench` compare them to the
+    /// day's baseline in `day_dir` and save them as the new baseline
+    pub fn finish(&self, day_dir: &str) {
+        match self.mode {
+            Mode::Off => (),
+            Mode::Time => print!("{}", self.report(None)),
+            Mode::Bench(_) => {
+                let path = baseline_path(day_dir);
+                let baseline = match Baseline::load(&path) {
+                    Ok(baseline) => Some(baseline),
+                    Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+                    Err(err) => {
+                        println!("Ignoring the unreadable baseline {:?}: {}", path, err);
+                        None
+                    }
+                };
+                print!("{}", self.report(baseline.as_ref()));
+                if let Err(err) = Baseline::from(self).save(&path) {
+                    println!("Could not save the baseline {:?}: {}", path, err);
+                }
+            }
+        }
+    }
+
+    /// A line per step, and against a baseline how each one changed
+    pub fn report(&self, baseline: Option<&Baseline>) -> String {
+        let width = self
+            .timings
+            .iter()
+            .map(|timing| timing.name.len())
+            .max()
+            .unwrap_or(0);
+        let mut ret = match self.mode {
+            Mode::Bench(samples) => format!("Timings (median of {} runs):\n", samples),
+            _ => String::from("Timings:\n"),
+        };
+        for timing in self.timings.iter() {
+            let mut line = format!(
+                "{:<width$}  {:>10.2?}",
+                timing.name,
+                timing.median,
+                width = width
+            );
+            if let Mode::Bench(_) = self.mode {
+                line += &format!("  ({:.2?} - {:.2?})", timing.min, timing.max);
+            }
+            if let Some(comparison) = baseline.and_then(|baseline| baseline.compare(timing)) {
+                line += &format!("  {}", comparison);
+            }
+            ret += &line;
+            ret += "\n";
+        }
+        if let Some(baseline) = baseline {
+            let regressions = baseline.regressions(self);
+            if regressions.is_empty() {
+                ret += "No regressions against the last run\n";
+            } else {
+                ret += &format!(
+                    "Regressions against the last run: {}\n",
+                    regressions.join(", ")
+                );
+            }
+        }
+        ret
+    }
+}
+
+/// A step's time now against its time in the baseline
+#[derive(Debug, PartialEq)]
+pub struct Comparison {
+    pub before: Duration,
+    pub after: Duration,
+}
+
+impl Comparison {
+    /// How much the time changed, as a fraction of the baseline
+    pub fn change(&self) -> f64 {
+        if self.before.is_zero() {
+            return 0.0;
+        }
+        (self.after.as_secs_f64() - self.before.as_secs_f64()) / self.before.as_secs_f64()
+    }
+
+    pub fn is_regression(&self) -> bool {
+        self.after >= REGRESSION_MINIMUM && self.change() > REGRESSION_THRESHOLD
+    }
+}
+
+impl std::fmt::Display for Comparison {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "was {:.2?}, {:+.1}%", self.before, self.change() * 100.0)?;
+        if self.is_regression() {
+            write!(f, " REGRESSION")?;
+        }
+        Ok(())
+    }
+}
+
+/// The median time of each step from a previous benchmark
+#[derive(Debug, PartialEq, Default)]
+pub struct Baseline {
+    medians: HashMap<String, Duration>,
+}
+
+pub fn baseline_path(day_dir: &str) -> PathBuf {
+    Path::new(day_dir).join(BASELINE_FILE)
+}
+
+impl From<&Timings> for Baseline {
+    fn from(timings: &Timings) -> Baseline {
+        Baseline {
+            medians: timings
+                .timings
+                .iter()
+                .map(|timing| (timing.name.clone(), timing.median))
+                .collect(),
+        }
+    }
+}
+
+impl Baseline {
+    /// Read a baseline saved by `save`, a line per step of `name<TAB>nanoseconds`
+    pub fn parse(text: &str) -> Result<Baseline, String> {
+        let mut medians = HashMap::new();
+        for (i, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (name, nanos) = line
+                .split_once('\t')
+                .and_then(|(name, nanos)| Some((name, nanos.trim().parse::<u64>().ok()?)))
+                .ok_or_else(|| format!("Line {} is not a name and a time: {:?}", i + 1, line))?;
+            medians.insert(String::from(name), Duration::from_nanos(nanos));
+        }
+        Ok(Baseline { medians })
+    }
+
+    pub fn load(path: &Path) -> std::io::Result<Baseline> {
+        Baseline::parse(&std::fs::read_to_string(path)?)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let mut names: Vec<&String> = self.medians.keys().collect();
+        names.sort();
+        let text: String = names
+            .into_iter()
+            .map(|name| format!("{}\t{}\n", name, self.medians[name].as_nanos()))
+            .collect();
+        std::fs::write(path, text)
+    }
+
+    /// How a step compares to its baseline, if the baseline has it
+    pub fn compare(&self, timing: &Timing) -> Option<Comparison> {
+        self.medians.get(&timing.name).map(|before| Comparison {
+            before: *before,
+            after: timing.median,
+        })
+    }
+
+    /// The names of the steps that got slower
+    pub fn regressions(&self, timings: &Timings) -> Vec<String> {
+        timings
+            .timings
+            .iter()
+            .filter(|timing| {
+                self.compare(timing)
+                    .is_some_and(|comparison| comparison.is_regression())
+            })
+            .map(|timing| timing.name.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn timings(mode: Mode, medians: &[(&str, u64)]) -> Timings {
+        Timings {
+            mode,
+            timings: medians
+                .iter()
+                .map(|(name, micros)| Timing {
+                    name: String::from(*name),
+                    median: Duration::from_micros(*micros),
+                    min: Duration::from_micros(*micros),
+                    max: Duration::from_micros(*micros),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_mode() {
+        assert_eq!(Mode::from_arg("--time"), Some(Mode::Time));
+        assert_eq!(Mode::from_arg("--bench"), Some(Mode::Bench(BENCH_SAMPLES)));
+        assert_eq!(Mode::from_arg("--what"), None);
+        assert_eq!(
+            Args::parse(std::iter::empty()),
+            Args {
+                mode: Mode::Off,
+                record: false
+            }
+        );
+        let args = vec![String::from("--record"), String::from("--bench")];
+        assert_eq!(
+            Args::parse(args.into_iter()),
+            Args {
+                mode: Mode::Bench(BENCH_SAMPLES),
+                record: true
+            }
+        );
+    }
+
+    #[test]
+    fn test_time() {
+        let mut runs = 0;
+        let mut timings = Timings::new(Mode::Bench(5));
+        let result = timings.time("count", || {
+            runs += 1;
+            runs
+        });
+        assert_eq!((runs, result), (5, 5));
+        let timing = &timings.timings()[0];
+        assert_eq!(timing.name, "count");
+        assert!(timing.min <= timing.median && timing.median <= timing.max);
+
+        let mut timings = Timings::new(Mode::Off);
+        assert_eq!(timings.time("once", || "result"), "result");
+        assert_eq!(timings.timings().len(), 1);
+    }
+
+    #[test]
+    fn test_baseline_round_trip() {
+        let baseline = Baseline::from(&timings(Mode::Time, &[("parse", 3), ("part a", 1500)]));
+        let path = std::env::temp_dir().join(format!("harness_test_{}.txt", std::process::id()));
+        baseline.save(&path).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "parse\t3000\npart a\t1500000\n"
+        );
+        assert_eq!(Baseline::load(&path).unwrap(), baseline);
+        std::fs::remove_file(&path).unwrap();
+        assert!(Baseline::parse("parse 3000").is_err());
+        assert_eq!(Baseline::parse("\n").unwrap(), Baseline::default());
+    }
+
+    #[test]
+    fn test_regressions() {
+        let baseline = Baseline::from(&timings(
+            Mode::Time,
+            &[
+                ("parse", 1000),
+                ("part a", 1000),
+                ("part b", 1),
+                ("gone", 5),
+            ],
+        ));
+        let now = timings(
+            Mode::Bench(3),
+            &[("parse", 1050), ("part a", 1200), ("part b", 3), ("new", 9)],
+        );
+        // part b tripled, but it's too quick to tell
+        assert_eq!(baseline.regressions(&now), ["part a"]);
+        let report = now.report(Some(&baseline));
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines[0], "Timings (median of 3 runs):");
+        assert!(lines[1].ends_with("was 1.00ms, +5.0%"), "{}", lines[1]);
+        assert!(lines[2].ends_with("was 1.00ms, +20.0% REGRESSION"));
+        assert!(lines[4].ends_with("(9.00µs - 9.00µs)"));
+        assert_eq!(lines[5], "Regressions against the last run: part a");
+        let report = now.report(None);
+        assert_eq!(report.lines().count(), 5);
+    }
+}
@@ -0,0 +1,365 @@
+// The bits of input splitting every day ends up writing: a value per line,
+// comma separated lists, records between blank lines and `key:val` pairs.
+// Everything reports where in the input it went wrong, with lines and
+// columns counting from 1 like an editor does.
+use std::str::FromStr;
+
+#[derive(Debug, PartialEq)]
+pub enum ParseError<E> {
+    /// A value that couldn't be parsed. `index` is which value on the line
+    /// it was, counting from 0, for lists with more than one.
+    Value {
+        line: usize,
+        column: usize,
+        index: usize,
+        text: String,
+        error: E,
+    },
+    /// A `key:val` pair without the `:`
+    MissingSeparator {
+        line: usize,
+        column: usize,
+        text: String,
+    },
+    /// A key that was already given in the same record
+    DuplicateKey {
+        line: usize,
+        column: usize,
+        key: String,
+    },
+}
+
+impl<E> ParseError<E> {
+    /// The line the error is on
+    pub fn line(&self) -> usize {
+        match self {
+            ParseError::Value { line, .. }
+            | ParseError::MissingSeparator { line, .. }
+            | ParseError::DuplicateKey { line, .. } => *line,
+        }
+    }
+
+    /// Move the error down `offset` lines, for when the text that was parsed
+    /// started part way through the input (like a `Record`)
+    pub fn offset_line(mut self, offset: usize) -> ParseError<E> {
+        match &mut self {
+            ParseError::Value { line, .. }
+            | ParseError::MissingSeparator { line, .. }
+            | ParseError::DuplicateKey { line, .. } => *line += offset,
+        }
+        self
+    }
+}
+
+impl<E: std::fmt::Display> std::fmt::Display for ParseError<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::Value {
+                line,
+                column,
+                text,
+                error,
+                ..
+            } => write!(f, "Line {}, column {}: {:?} {}", line, column, text, error),
+            ParseError::MissingSeparator { line, column, text } => write!(
+                f,
+                "Line {}, column {}: {:?} is not in key:val format",
+                line, column, text
+            ),
+            ParseError::DuplicateKey { line, column, key } => {
+                write!(f, "Line {}, column {}: {:?} given twice", line, column, key)
+            }
+        }
+    }
+}
+
+impl<E: std::fmt::Debug + std::fmt::Display> std::error::Error for ParseError<E> {}
+
+// The column `part` starts at, where `part` is a slice of `line`
+fn column(line: &str, part: &str) -> usize {
+    let start = part.as_ptr() as usize - line.as_ptr() as usize;
+    line[..start].chars().count() + 1
+}
+
+/// Parse each line that isn't blank with `parse`, ignoring whitespace around
+/// it. Blank lines (like the one a trailing newline leaves) are skipped.
+pub fn parse_lines<T, E, F>(text: &str, mut parse: F) -> Result<Vec<T>, ParseError<E>>
+where
+    F: FnMut(&str) -> Result<T, E>,
+{
+    let mut ret = vec![];
+    for (i, line) in text.lines().enumerate() {
+        let value = line.trim();
+        if value.is_empty() {
+            continue;
+        }
+        ret.push(parse(value).map_err(|error| ParseError::Value {
+            line: i + 1,
+            column: column(line, value),
+            index: 0,
+            text: String::from(value),
+            error,
+        })?);
+    }
+    Ok(ret)
+}
+
+/// A number (or anything else `FromStr`) on each line
+pub fn numbers<T: FromStr>(text: &str) -> Result<Vec<T>, ParseError<T::Err>> {
+    parse_lines(text, str::parse)
+}
+
+/// Values on one line split by `separator`, where `placeholder` stands in for
+/// a missing value, like the `x`s in `7,13,x,x,59`
+pub fn separated_with_placeholder<T: FromStr>(
+    line: &str,
+    separator: char,
+    placeholder: &str,
+) -> Result<Vec<Option<T>>, ParseError<T::Err>> {
+    line.split(separator)
+        .enumerate()
+        .map(|(index, part)| {
+            let value = part.trim();
+            if value == placeholder {
+                return Ok(None);
+            }
+            value
+                .parse::<T>()
+                .map(Some)
+                .map_err(|error| ParseError::Value {
+                    line: 1,
+                    column: column(line, value),
+                    index,
+                    text: String::from(value),
+                    error,
+                })
+        })
+        .collect()
+}
+
+/// Values on one line split by `separator`
+pub fn separated<T: FromStr>(line: &str, separator: char) -> Result<Vec<T>, ParseError<T::Err>> {
+    line.split(separator)
+        .enumerate()
+        .map(|(index, part)| {
+            let value = part.trim();
+            value.parse::<T>().map_err(|error| ParseError::Value {
+                line: 1,
+                column: column(line, value),
+                index,
+                text: String::from(value),
+                error,
+            })
+        })
+        .collect()
+}
+
+/// A run of lines with blank lines either side
+#[derive(Debug, PartialEq)]
+pub struct Record<'a> {
+    /// The line the record starts on
+    pub line: usize,
+    pub text: &'a str,
+}
+
+impl<'a> Record<'a> {
+    pub fn lines(&self) -> impl Iterator<Item = &'a str> {
+        self.text.lines()
+    }
+}
+
+/// Split the text into records separated by blank (or whitespace only) lines.
+/// Any number of blank lines between, before or after records is ignored
+/// rather than making empty records.
+pub fn records(text: &str) -> Vec<Record<'_>> {
+    let mut ret = vec![];
+    // (first line, byte offset) of the record we're in, if any
+    let mut current: Option<(usize, usize)> = None;
+    let mut offset = 0;
+    for (i, line) in text.split_inclusive('\n').enumerate() {
+        if line.trim().is_empty() {
+            if let Some((first, start)) = current.take() {
+                ret.push(Record {
+                    line: first,
+                    text: &text[start..offset],
+                });
+            }
+        } else if current.is_none() {
+            current = Some((i + 1, offset));
+        }
+        offset += line.len();
+    }
+    if let Some((first, start)) = current {
+        ret.push(Record {
+            line: first,
+            text: &text[start..],
+        });
+    }
+    ret
+}
+
+/// Whitespace separated `key:val` pairs, over as many lines as there are, in
+/// the order they're given. Only the first `:` splits, so values can have
+/// their own. A key given twice is an error.
+pub fn key_values(text: &str) -> Result<Vec<(&str, &str)>, ParseError<std::convert::Infallible>> {
+    let mut ret: Vec<(&str, &str)> = vec![];
+    for (i, line) in text.lines().enumerate() {
+        for pair in line.split_whitespace() {
+            let (key, val) = key_value(i, line, pair)?;
+            if ret.iter().any(|(seen, _)| *seen == key) {
+                return Err(ParseError::DuplicateKey {
+                    line: i + 1,
+                    column: column(line, pair),
+                    key: String::from(key),
+                });
+            }
+            ret.push((key, val));
+        }
+    }
+    Ok(ret)
+}
+
+/// `key_values`, but a key can be given more than once, with every pair kept
+pub fn key_value_pairs(
+    text: &str,
+) -> Result<Vec<(&str, &str)>, ParseError<std::convert::Infallible>> {
+    let mut ret: Vec<(&str, &str)> = vec![];
+    for (i, line) in text.lines().enumerate() {
+        for pair in line.split_whitespace() {
+            ret.push(key_value(i, line, pair)?);
+        }
+    }
+    Ok(ret)
+}
+
+// Split one `key:val` pair found on line `i` (counting from 0)
+fn key_value<'a>(
+    i: usize,
+    line: &str,
+    pair: &'a str,
+) -> Result<(&'a str, &'a str), ParseError<std::convert::Infallible>> {
+    pair.split_once(':')
+        .ok_or_else(|| ParseError::MissingSeparator {
+            line: i + 1,
+            column: column(line, pair),
+            text: String::from(pair),
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_numbers() {
+        assert_eq!(
+            numbers::<i32>("1721\n979\n-366\n"),
+            Ok(vec![1721, 979, -366])
+        );
+        assert_eq!(numbers::<u8>(" 1 \r\n\n2"), Ok(vec![1, 2]));
+        assert_eq!(numbers::<u8>(""), Ok(vec![]));
+        let err = numbers::<u8>("1\n\n  300\n").unwrap_err();
+        assert_eq!(
+            (err.line(), err.to_string()),
+            (
+                3,
+                String::from("Line 3, column 3: \"300\" number too large to fit in target type")
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_lines() {
+        let lengths = parse_lines("ab\ncde", |line| Ok::<usize, ()>(line.len()));
+        assert_eq!(lengths, Ok(vec![2, 3]));
+        let err = parse_lines(
+            "ab\n cde",
+            |line| if line.len() < 3 { Ok(1) } else { Err("long") },
+        );
+        assert_eq!(
+            err,
+            Err(ParseError::Value {
+                line: 2,
+                column: 2,
+                index: 0,
+                text: String::from("cde"),
+                error: "long"
+            })
+        );
+    }
+
+    #[test]
+    fn test_separated() {
+        assert_eq!(
+            separated_with_placeholder::<u32>("7,13,x,x,59", ',', "x"),
+            Ok(vec![Some(7), Some(13), None, None, Some(59)])
+        );
+        assert_eq!(separated::<u32>("1, 2,3", ','), Ok(vec![1, 2, 3]));
+        match separated_with_placeholder::<u32>("7,13,y", ',', "x") {
+            Err(ParseError::Value {
+                line,
+                column,
+                index,
+                text,
+                ..
+            }) => assert_eq!((line, column, index, &text[..]), (1, 6, 2, "y")),
+            other => panic!("Expected a bad value, got {:?}", other),
+        }
+        assert_eq!(separated::<u32>("1,,2", ',').unwrap_err().line(), 1);
+    }
+
+    #[test]
+    fn test_records() {
+        let text = "\n\nab\nac\n\n\n  \nb\n\n";
+        let found = records(text);
+        assert_eq!(
+            found,
+            [
+                Record {
+                    line: 3,
+                    text: "ab\nac\n"
+                },
+                Record {
+                    line: 8,
+                    text: "b\n"
+                }
+            ]
+        );
+        assert_eq!(found[0].lines().collect::<Vec<&str>>(), ["ab", "ac"]);
+        assert_eq!(records("a\r\n\r\nb")[1], Record { line: 3, text: "b" });
+        assert!(records("").is_empty());
+        assert!(records("\n \n").is_empty());
+    }
+
+    #[test]
+    fn test_key_values() {
+        assert_eq!(
+            key_values("ecl:gry pid:860033327\nhcl:#fffffd time:12:30"),
+            Ok(vec![
+                ("ecl", "gry"),
+                ("pid", "860033327"),
+                ("hcl", "#fffffd"),
+                ("time", "12:30")
+            ])
+        );
+        assert_eq!(
+            key_values("a:1\nb:2  c3"),
+            Err(ParseError::MissingSeparator {
+                line: 2,
+                column: 6,
+                text: String::from("c3")
+            })
+        );
+        let err = key_values("a:1 a:2").unwrap_err().offset_line(4);
+        assert_eq!(err.to_string(), "Line 5, column 5: \"a\" given twice");
+        assert_eq!(key_value_pairs("a:1 a:2"), Ok(vec![("a", "1"), ("a", "2")]));
+        assert_eq!(
+            key_value_pairs("a:1\n\nb"),
+            Err(ParseError::MissingSeparator {
+                line: 3,
+                column: 1,
+                text: String::from("b")
+            })
+        );
+    }
+}